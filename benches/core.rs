@@ -0,0 +1,149 @@
+use rust_lcp::encoding::init_coefficients_default;
+use rust_lcp::String;
+use rust_lcp::config::Config;
+use rust_lcp::stream::{CoreReader, CoreDeepener, CoreScanner};
+use rust_lcp::parallel::ThreadPool;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use std::{env, fs, thread};
+
+
+/// Allocator that counts the allocations, so the heap usage of the cores can be reported.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+
+/// Length of the generated sequence, if no FASTA file is given.
+const SEQUENCE_LENGTH: usize = 1 << 24;
+/// Number of levels the sequence is deepened.
+const LEVEL: u32 = 5;
+
+
+/// This function reads the sequences of a FASTA file as a single sequence.
+///
+/// # Arguments
+///
+/// * `path` - path of the FASTA file, e.g. a full chromosome.
+///
+fn read_fasta(path: &str) -> Vec<u8> {
+	fs::read(path).expect("cannot read FASTA file")
+		.split(|ch| *ch == b'\n')
+		.filter(|line| !line.starts_with(b">"))
+		.flat_map(|line| line.iter().copied().filter(|ch| !ch.is_ascii_whitespace()))
+		.collect()
+}
+
+
+/// This function generates a pseudo-random DNA sequence of given length.
+///
+/// # Arguments
+///
+/// * `length` - length of the sequence.
+///
+fn generate(length: usize) -> Vec<u8> {
+	let mut state: u64 = 0x2545F4914F6CDD1D;
+	(0..length).map(|_| {
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		b"ACGT"[(state >> 62) as usize]
+	}).collect()
+}
+
+
+/// Usage: `cargo bench --bench core [-- <chromosome.fa>]`
+fn main() {
+
+	let sequence = match env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+		Some(path) => read_fasta(&path),
+		None => generate(SEQUENCE_LENGTH),
+	};
+	let alphabet = init_coefficients_default(false).unwrap();
+
+	println!("sequence length: {}", sequence.len());
+
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+	let time = Instant::now();
+	let mut string = String::from_u8(&sequence, &alphabet);
+
+	report(&string, time, ALLOCATIONS.load(Ordering::Relaxed) - allocations);
+
+	// scanning yields the positions and fingerprints of the level-1 cores, without creating them
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+	let time = Instant::now();
+	let checksum = CoreScanner::new(&sequence, &alphabet, 0).fold(0, |checksum: u64, (start, end, fingerprint)| checksum ^ fingerprint ^ ( start ^ end ) as u64);
+
+	println!(
+		"scanning level 1: {:x} checksum, {} allocations, {:?}",
+		checksum, ALLOCATIONS.load(Ordering::Relaxed) - allocations, time.elapsed()
+	);
+
+	for _ in 1..LEVEL {
+		let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+		let time = Instant::now();
+		string.deepen();
+
+		report(&string, time, ALLOCATIONS.load(Ordering::Relaxed) - allocations);
+	}
+
+	// parallel parsing gives the same cores, so the cores of the last level are compared
+	let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+	let pool = ThreadPool::new(threads);
+	let time = Instant::now();
+	let mut parallel = String::from_u8_parallel(&sequence, &alphabet, &pool);
+	parallel.deepen_parallel(LEVEL - 1, &pool);
+	assert_eq!(parallel.to_bytes(), string.to_bytes());
+
+	println!("parallel level {}: {} cores, {} threads, {:?}", LEVEL, parallel.cores.len(), threads, time.elapsed());
+
+	// streaming pipeline keeps only the windows in progress, instead of whole levels
+	let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+	let time = Instant::now();
+	let reader = CoreReader::new(sequence.as_slice(), &alphabet);
+	let mut count: usize = 0;
+	for core in CoreDeepener::new(reader, Config::default(), LEVEL - 1) {
+		core.unwrap();
+		count += 1;
+	}
+
+	println!(
+		"streaming level {}: {} cores, {} allocations, {:?}",
+		LEVEL, count, ALLOCATIONS.load(Ordering::Relaxed) - allocations, time.elapsed()
+	);
+}
+
+
+/// This function prints the cores and allocations of a level. Before inline storage, each core
+/// created in a level had a heap allocation, including the ones created by `Core::from_cores`
+/// and replaced by `Core::compress` while deepening.
+///
+/// # Arguments
+///
+/// * `string` - String that is processed to the level.
+/// * `time` - the time processing of the level started.
+/// * `allocations` - number of allocations while processing the level.
+///
+fn report(string: &String, time: Instant, allocations: usize) {
+	let heap = string.cores.iter().filter(|core| !core.blocks.is_inline()).count();
+	println!(
+		"level {}: {} cores ({} in heap), {} allocations, {:.2} allocations per core, {:?}",
+		string.level, string.cores.len(), heap, allocations, allocations as f64 / string.cores.len().max(1) as f64, time.elapsed()
+	);
+}
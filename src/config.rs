@@ -0,0 +1,71 @@
+use crate::statics::{COMPRESSION_ITERATION_COUNT, MAX_COMPRESSION_ITERATION_COUNT, MAX_SPAN};
+use crate::error::{Error, Result};
+
+
+/// Runtime configuration of the lcp algorithm, used while increasing the level of a String.
+/// Default configuration uses `COMPRESSION_ITERATION_COUNT` iterations of deterministic coin tossing and
+/// drops the cores whose window spans `MAX_SPAN` or more characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+	/// Number of deterministic coin tossing iterations done at each level.
+	compression_iteration_count: usize,
+	/// Maximum number of characters that a candidate window of a core can span, None if unlimited.
+	max_span: Option<usize>,
+}
+
+
+impl Config {
+
+	/// Constructor of Config with given number of deterministic coin tossing iterations.
+	/// 
+	/// # Arguments
+	/// 
+	/// * `compression_iteration_count` - number of compression iterations per level, in range [1, MAX_COMPRESSION_ITERATION_COUNT].
+	///
+	pub fn new(compression_iteration_count: usize) -> Result<Self> {
+		if !( 1..=MAX_COMPRESSION_ITERATION_COUNT ).contains(&compression_iteration_count) {
+			return Err(Error::InvalidIterationCount { count: compression_iteration_count });
+		}
+
+		Ok(Config { compression_iteration_count, max_span: Some(MAX_SPAN) })
+	}
+
+	/// This function returns the configuration with given maximum span. While increasing the level,
+	/// candidate cores whose window (the 5 cores compared by the rules) spans `max_span` or more
+	/// characters are dropped, which limits the size of the cores in long repeats.
+	/// 
+	/// # Arguments
+	/// 
+	/// * `max_span` - maximum span in characters, None for unlimited.
+	///
+	pub fn with_max_span(self, max_span: Option<usize>) -> Self {
+		Config { max_span, ..self }
+	}
+
+	/// This function returns the maximum span of candidate cores, None if unlimited.
+	#[inline(always)]
+	pub fn max_span(&self) -> Option<usize> {
+		self.max_span
+	}
+
+	/// This function returns the number of deterministic coin tossing iterations done at each level.
+	#[inline(always)]
+	pub fn compression_iteration_count(&self) -> usize {
+		self.compression_iteration_count
+	}
+
+	/// This function returns the number of cores that are concatinated into a new core, which is 3 cores
+	/// in the middle of the window and the left neighbours affecting their labels during compression.
+	#[inline(always)]
+	pub fn core_length(&self) -> usize {
+		3 + self.compression_iteration_count
+	}
+}
+
+
+impl Default for Config {
+
+	fn default() -> Self {
+		Config { compression_iteration_count: COMPRESSION_ITERATION_COUNT, max_span: Some(MAX_SPAN) }
+	}
+}
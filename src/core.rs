@@ -1,16 +1,55 @@
-use crate::statics::LABELS;
-use crate::statics::DICT_BIT_SIZE;
-use crate::statics::SIZE_PER_BLOCK;
-use std::mem;
+use crate::statics::{SIZE_PER_BLOCK, INLINE_BLOCK_NUMBER, SERIALIZATION_VERSION};
+use crate::encoding::Alphabet;
+use crate::error::{Error, Result};
+use crate::serialize::{write_varint, read_usize, read_version};
 use std::cmp;
+use std::fmt;
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher, DefaultHasher};
 
-#[derive(Eq, Ord)]
+
+/// Storage of the blocks of a Core.
+/// Labels that fit in `INLINE_BLOCK_NUMBER` blocks, which is the common case after compression,
+/// are stored inline without any heap allocation. Longer labels, such as the ones of long runs
+/// or of the cores before compression at higher levels, are stored in an owned buffer.
+#[derive(Clone)]
+pub enum Blocks {
+	Inline([u8; INLINE_BLOCK_NUMBER]),
+	Heap(Box<[u8]>),
+}
+
+
+impl Blocks {
+
+	/// Constructor of cleared Blocks that can store `block_number` blocks.
+	///
+	/// # Arguments
+	/// * `block_number` - number of blocks to be stored.
+	///
+	#[inline(always)]
+	fn new(block_number: usize) -> Self {
+		if block_number <= INLINE_BLOCK_NUMBER {
+			Blocks::Inline([0; INLINE_BLOCK_NUMBER])
+		} else {
+			Blocks::Heap(vec![0; block_number].into_boxed_slice())
+		}
+	}
+
+	/// This function returns whether the blocks are stored inline, without heap allocation.
+	#[inline(always)]
+	#[allow(dead_code)]
+	pub fn is_inline(&self) -> bool {
+		matches!(self, Blocks::Inline(_))
+	}
+}
+
+
+#[derive(Clone)]
 pub struct Core {
-	/// This pointer stores the addres of the blocks
-	/// that is being used to define the substring with
-	/// either character encodings or with sub-level cores.
-	pub ptr: *mut u8,
+	/// This variable stores the blocks that are being used
+	/// to define the substring with either character encodings
+	/// or with sub-level cores.
+	pub blocks: Blocks,
 	/// This variable is the number of blocks (8bits) being
 	/// used to store bit representation of substring/sub-level cores.
 	pub block_number: usize,
@@ -40,49 +79,14 @@ impl Core {
 	/// 
 	/// * `start` - start position of given substring within original string.
 	/// * `str` - substring that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to convert characters to labels.
+	///
+	/// Returns `Error::UnknownSymbol` if a character is not part of the alphabet. Positions are counted in characters.
 	///
 	#[allow(dead_code)]
-	pub fn from_str(start: usize, string: &str) -> Self {
-
-		unsafe {
-
-			let block_number: usize = ( string.len() * DICT_BIT_SIZE - 1) / SIZE_PER_BLOCK + 1;
-			let start_index: usize = block_number * SIZE_PER_BLOCK - string.len() * DICT_BIT_SIZE;
-
-			// create a new mutable buffer with capacity `block_number`
-			let mut buf = Vec::with_capacity(block_number.try_into().unwrap());
-			// take a mutable pointer to the buffer
-			let ptr: *mut u8 = buf.as_mut_ptr();
-			// prevent the buffer from being deallocated when it goes out of scope
-			mem::forget(buf);
-
-			// clear dumps
-			for i in 0..block_number {
-				*ptr.add(i.try_into().unwrap()) &= 0;
-			}
-
-			// Encoding string to bits
-			let mut index: usize = 0;
-
-			for ch in string.chars() { 
-				if SIZE_PER_BLOCK - ( start_index + index ) % SIZE_PER_BLOCK >= DICT_BIT_SIZE {
-					*ptr.add( ( start_index + index) / SIZE_PER_BLOCK ) |= ( ( LABELS[ch as usize] as usize ) << ( SIZE_PER_BLOCK - ( start_index + index + DICT_BIT_SIZE ) % SIZE_PER_BLOCK ) % SIZE_PER_BLOCK ) as u8;	
-				} else {
-					*ptr.add( ( start_index + index ) / SIZE_PER_BLOCK ) |= ( ( LABELS[ch as usize] as usize ) >> ( start_index + index + DICT_BIT_SIZE ) % SIZE_PER_BLOCK ) as u8;
-					*ptr.add( ( start_index + index) / SIZE_PER_BLOCK  + 1 ) |= ( ( LABELS[ch as usize] as usize ) << ( SIZE_PER_BLOCK - ( start_index + index + DICT_BIT_SIZE ) % SIZE_PER_BLOCK ) % SIZE_PER_BLOCK ) as u8;
-				}
-				
-				index += DICT_BIT_SIZE;
-			}
-
-			Core {
-				ptr: ptr,
-				block_number: block_number,
-				start_index: start_index,
-				start: start,
-				end: start+string.len()
-			}
-		}
+	pub fn from_str(start: usize, string: &str, alphabet: &Alphabet) -> Result<Self> {
+		let labels: Vec<u32> = string.chars().map(|ch| Self::label(ch, alphabet)).collect::<Result<Vec<u32>>>()?;
+		Ok(Self::from_labels(start, &labels, alphabet.dict_bit_size))
 	}
 
 
@@ -93,49 +97,99 @@ impl Core {
 	/// 
 	/// * `start` - start position of given substring within original string.
 	/// * `str` - substring that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to convert characters to labels.
+	///
+	/// Returns `Error::UnknownSymbol` if a character is not part of the alphabet.
 	///
 	#[allow(dead_code)]
-	pub fn from_u8(start: usize, string: &[u8]) -> Self {
+	pub fn from_u8(start: usize, string: &[u8], alphabet: &Alphabet) -> Result<Self> {
+		match string.iter().find(|&&ch| alphabet.label(ch).is_none()) {
+			Some(&ch) => Err(Error::UnknownSymbol { character: char::from(ch) }),
+			None => Ok(Self::from_u8_unchecked(start, string, alphabet)),
+		}
+	}
 
-		unsafe {
+	/// Constructor of Core from [u8] as `from_u8`, without checking the characters. It is used by the parsers,
+	/// which have already applied the ambiguity policy, so every character is part of the alphabet.
+	///
+	/// # Arguments
+	/// 
+	/// * `start` - start position of given substring within original string.
+	/// * `str` - substring whose characters are all part of the alphabet.
+	/// * `alphabet` - alphabet encoding used to convert characters to labels.
+	///
+	pub(crate) fn from_u8_unchecked(start: usize, string: &[u8], alphabet: &Alphabet) -> Self {
 
-			let block_number: usize = ( string.len() * DICT_BIT_SIZE - 1) / SIZE_PER_BLOCK + 1;
-			let start_index: usize = block_number * SIZE_PER_BLOCK - string.len() * DICT_BIT_SIZE;
+		debug_assert!(string.iter().all(|&ch| alphabet.label(ch).is_some()), "core contains a character that is not part of the alphabet");
 
-			// create a new mutable buffer with capacity `block_number`
-			let mut buf = Vec::with_capacity(block_number.try_into().unwrap());
-			// take a mutable pointer to the buffer
-			let ptr: *mut u8 = buf.as_mut_ptr();
-			// prevent the buffer from being deallocated when it goes out of scope
-			mem::forget(buf);
+		let block_number: usize = ( string.len() * alphabet.dict_bit_size - 1) / SIZE_PER_BLOCK + 1;
+		let start_index: usize = block_number * SIZE_PER_BLOCK - string.len() * alphabet.dict_bit_size;
 
-			// clear dumps
-			for i in 0..block_number {
-				*ptr.add(i.try_into().unwrap()) &= 0;
-			}
+		let mut core = Core {
+			blocks: Blocks::new(block_number),
+			block_number,
+			start_index,
+			start,
+			end: start+string.len()
+		};
+		let blocks = core.get_blocks_mut();
 
-			// Encoding string to bits
-			let mut index: usize = 0;
+		// Encoding string to bits
+		let mut index: usize = 0;
 
-			for ch in string { 
-				if SIZE_PER_BLOCK - ( start_index + index ) % SIZE_PER_BLOCK >= DICT_BIT_SIZE {
-					*ptr.add( ( start_index + index) / SIZE_PER_BLOCK ) |= ( ( LABELS[*ch as usize] as usize ) << ( SIZE_PER_BLOCK - ( start_index + index + DICT_BIT_SIZE ) % SIZE_PER_BLOCK ) % SIZE_PER_BLOCK ) as u8;	
-				} else {
-					*ptr.add( ( start_index + index ) / SIZE_PER_BLOCK ) |= ( ( LABELS[*ch as usize] as usize ) >> ( start_index + index + DICT_BIT_SIZE ) % SIZE_PER_BLOCK ) as u8;
-					*ptr.add( ( start_index + index) / SIZE_PER_BLOCK  + 1 ) |= ( ( LABELS[*ch as usize] as usize ) << ( SIZE_PER_BLOCK - ( start_index + index + DICT_BIT_SIZE ) % SIZE_PER_BLOCK ) % SIZE_PER_BLOCK ) as u8;
-				}
-				
-				index += DICT_BIT_SIZE;
+		for ch in string { 
+			if SIZE_PER_BLOCK - ( start_index + index ) % SIZE_PER_BLOCK >= alphabet.dict_bit_size {
+				blocks[ ( start_index + index) / SIZE_PER_BLOCK ] |= ( ( alphabet.labels[*ch as usize] as usize ) << ( ( SIZE_PER_BLOCK - ( start_index + index + alphabet.dict_bit_size ) % SIZE_PER_BLOCK ) % SIZE_PER_BLOCK ) ) as u8;	
+			} else {
+				blocks[ ( start_index + index ) / SIZE_PER_BLOCK ] |= ( ( alphabet.labels[*ch as usize] as usize ) >> ( ( start_index + index + alphabet.dict_bit_size ) % SIZE_PER_BLOCK ) ) as u8;
+				blocks[ ( start_index + index) / SIZE_PER_BLOCK  + 1 ] |= ( ( alphabet.labels[*ch as usize] as usize ) << ( ( SIZE_PER_BLOCK - ( start_index + index + alphabet.dict_bit_size ) % SIZE_PER_BLOCK ) % SIZE_PER_BLOCK ) ) as u8;
 			}
+			
+			index += alphabet.dict_bit_size;
+		}
+
+		core
+	}
+
 
-			Core {
-				ptr: ptr,
-				block_number: block_number,
-				start_index: start_index,
-				start: start,
-				end: start+string.len()
+	/// Constructor of Core from labels.
+	/// Labels are already encoded characters, each of them is stored with `bit_size` bits. Unlike other
+	/// constructors, label width is not limited to a single block, so it can be used with large alphabets
+	/// such as the ones built from Unicode text.
+	///
+	/// # Arguments
+	///
+	/// * `start` - start position of given substring within original string.
+	/// * `labels` - labels of the characters of the substring.
+	/// * `bit_size` - number of bits used to store each label.
+	///
+	#[allow(dead_code)]
+	pub fn from_labels(start: usize, labels: &[u32], bit_size: usize) -> Self {
+
+		let bit_count: usize = labels.len() * bit_size;
+		let block_number: usize = ( bit_count - 1 ) / SIZE_PER_BLOCK + 1;
+		let start_index: usize = block_number * SIZE_PER_BLOCK - bit_count;
+
+		let mut core = Core {
+			blocks: Blocks::new(block_number),
+			block_number,
+			start_index,
+			start,
+			end: start+labels.len()
+		};
+		let blocks = core.get_blocks_mut();
+
+		// Encoding labels to bits, starting from the most significant bit of each label
+		let mut index: usize = start_index;
+
+		for label in labels {
+			for shift in (0..bit_size).rev() {
+				blocks[ index / SIZE_PER_BLOCK ] |= ( ( ( label >> shift ) & 1 ) as u8 ) << ( SIZE_PER_BLOCK - index % SIZE_PER_BLOCK - 1 );
+				index += 1;
 			}
 		}
+
+		core
 	}
 
 
@@ -147,30 +201,37 @@ impl Core {
 	/// 
 	/// * `start` - index of given character within original string.
 	/// * `ch` - character that will be used to create Core.
+	/// * `alphabet` - alphabet encoding used to convert character to label.
+	///
+	/// Returns `Error::UnknownSymbol` if the character is not part of the alphabet.
 	///
 	#[allow(dead_code)]
-	pub fn from_char(start: usize, ch: char) -> Self {
-
-		unsafe {
-
-			// create a new mutable buffer with capacity `block_number`
-			let mut buf = Vec::with_capacity(1);
-			// take a mutable pointer to the buffer
-			let ptr: *mut u8 = buf.as_mut_ptr();
-			// prevent the buffer from being deallocated when it goes out of scope
-			mem::forget(buf);
-
-			*ptr.add(0) &= 0;
-			*ptr.add(0) |= LABELS[ch as usize] as u8;
-
-			Core {
-				ptr: ptr,
-				block_number: 1,
-				start_index: SIZE_PER_BLOCK - DICT_BIT_SIZE,
-				start: start,
-				end: start+1
-			}
-		}
+	pub fn from_char(start: usize, ch: char, alphabet: &Alphabet) -> Result<Self> {
+
+		let mut blocks = [0; INLINE_BLOCK_NUMBER];
+		blocks[0] = Self::label(ch, alphabet)? as u8;
+
+		Ok(Core {
+			blocks: Blocks::Inline(blocks),
+			block_number: 1,
+			start_index: SIZE_PER_BLOCK - alphabet.dict_bit_size,
+			start,
+			end: start+1
+		})
+	}
+
+	/// This function returns the label of a character, which should be part of the alphabet.
+	///
+	/// # Arguments
+	///
+	/// * `ch` - the character, characters that do not fit in a single byte are not part of any alphabet.
+	/// * `alphabet` - alphabet encoding used to convert characters to labels.
+	///
+	fn label(ch: char, alphabet: &Alphabet) -> Result<u32> {
+		u8::try_from(ch).ok()
+			.and_then(|byte| alphabet.label(byte))
+			.map(|label| label as u32)
+			.ok_or(Error::UnknownSymbol { character: ch })
 	}
 
 	/// Constructor of Core from Cores.
@@ -187,62 +248,50 @@ impl Core {
 	#[allow(dead_code)]
 	pub fn from_cores(cores: &[Core]) -> Self {
 
-		unsafe {
-
-			let bit_count: usize = cores.iter().map(|s| s.get_bit_count()).sum();
-			let block_number = ( bit_count - 1 ) / SIZE_PER_BLOCK + 1;
-			let start_index = block_number * SIZE_PER_BLOCK - bit_count;
-
-			// create a new mutable buffer with capacity `block_number`
-			let mut buf = Vec::with_capacity(block_number);
-			// take a mutable pointer to the buffer
-			let ptr: *mut u8 = buf.as_mut_ptr();
-			// prevent the buffer from being deallocated when it goes out of scope
-			mem::forget(buf);
-
-			// clear dumps
-			for i in 0..block_number {
-				*ptr.add(i.try_into().unwrap()) &= 0;
-			}
-
-			let mut index: usize = block_number * SIZE_PER_BLOCK - 1;
-
-			for core in cores.iter().rev() {
-				for (i, block) in core.get_blocks().iter().enumerate().rev() {
-					if index >= SIZE_PER_BLOCK {
-						*ptr.add( index / SIZE_PER_BLOCK ) |= block << ( SIZE_PER_BLOCK - index % SIZE_PER_BLOCK - 1 );
-						if index % SIZE_PER_BLOCK != SIZE_PER_BLOCK - 1 {
-							*ptr.add( index / SIZE_PER_BLOCK - 1) |= block >> ( index % SIZE_PER_BLOCK  + 1 );
-						}
-					} else {
-						*ptr.add( index / SIZE_PER_BLOCK ) |= block << ( SIZE_PER_BLOCK - index % SIZE_PER_BLOCK - 1);
-					}
-					if i == 0 {
-						if index > SIZE_PER_BLOCK - core.start_index {
-							index -= SIZE_PER_BLOCK - core.start_index;
-						}
+		let bit_count: usize = cores.iter().map(|s| s.get_bit_count()).sum();
+		let block_number = ( bit_count - 1 ) / SIZE_PER_BLOCK + 1;
+		let start_index = block_number * SIZE_PER_BLOCK - bit_count;
+
+		let mut result = Core {
+			blocks: Blocks::new(block_number),
+			block_number,
+			start_index,
+			start: cores[0].start,
+			end: cores.last().unwrap().end
+		};
+		let blocks = result.get_blocks_mut();
+
+		let mut index: usize = block_number * SIZE_PER_BLOCK - 1;
+
+		for core in cores.iter().rev() {
+			for (i, block) in core.get_blocks().iter().enumerate().rev() {
+				if index >= SIZE_PER_BLOCK {
+					blocks[ index / SIZE_PER_BLOCK ] |= block << ( SIZE_PER_BLOCK - index % SIZE_PER_BLOCK - 1 );
+					if index % SIZE_PER_BLOCK != SIZE_PER_BLOCK - 1 {
+						blocks[ index / SIZE_PER_BLOCK - 1] |= block >> ( index % SIZE_PER_BLOCK  + 1 );
 					}
-					else {
-						index -= SIZE_PER_BLOCK;
+				} else {
+					blocks[ index / SIZE_PER_BLOCK ] |= block << ( SIZE_PER_BLOCK - index % SIZE_PER_BLOCK - 1);
+				}
+				if i == 0 {
+					if index > SIZE_PER_BLOCK - core.start_index {
+						index -= SIZE_PER_BLOCK - core.start_index;
 					}
 				}
-			}
-
-			Core {
-				ptr: ptr,
-				block_number: block_number,
-				start_index: start_index,
-				start: cores[0].start,
-				end: cores.last().unwrap().end
+				else {
+					index -= SIZE_PER_BLOCK;
+				}
 			}
 		}
+
+		result
 	}
 
 
 	/// Compression of core with respect to another Core.
 	/// The compression is done accordinly:
-	/// 	1 - Find index of first bit differs in another Core in same index starting from right.
-	/// 	2 - Add the differing index to the right (index >> 1 | bit). 
+	///     1 - Find index of first bit differs in another Core in same index starting from right.
+	///     2 - Add the differing index to the right (index >> 1 | bit). 
 	/// The Core will be modified according to the value found by compression.
 	///
 	/// # Arguments
@@ -254,10 +303,10 @@ impl Core {
 		// declare variables
 		let mut o_block_index = other.block_number - 1;
 		let mut t_block_index = self.block_number - 1;
-		let o_values = unsafe { std::slice::from_raw_parts(other.ptr, other.block_number as usize) };
-		let t_values = unsafe { std::slice::from_raw_parts(self.ptr, self.block_number as usize) };
-		let mut o: u8 = o_values[o_block_index as usize];
-		let mut t: u8 = t_values[t_block_index as usize];
+		let o_values = other.get_blocks();
+		let t_values = self.get_blocks();
+		let mut o: u8 = o_values[o_block_index];
+		let mut t: u8 = t_values[t_block_index];
 		let mut helper_var1: usize = 0;
 		let mut helper_var2: usize;
 
@@ -267,8 +316,8 @@ impl Core {
 		while o_block_index > 0 && t_block_index > 0 && o == t {
 			o_block_index -= 1;
 			t_block_index -= 1;
-			o = o_values[o_block_index as usize];
-			t = t_values[t_block_index as usize];
+			o = o_values[o_block_index];
+			t = t_values[t_block_index];
 			helper_var1 += SIZE_PER_BLOCK;
 		}
 
@@ -317,37 +366,16 @@ impl Core {
 
 		// Compressed value is: index
 
-		// deallocate previous core
-		unsafe { Vec::from_raw_parts(self.ptr, self.block_number as usize, self.block_number as usize); }
-
 		// Change this object according to  the new values represents compressed version.
+		// Previous blocks are released (if they were allocated in heap) as they are replaced.
 		self.block_number = (helper_var1 - 1) / SIZE_PER_BLOCK + 1;
 		self.start_index = self.block_number * SIZE_PER_BLOCK - helper_var1;
+		self.blocks = Blocks::new(self.block_number);
 
-		// create a new mutable buffer with capacity `block_number`
-		let mut buf = Vec::with_capacity(self.block_number.try_into().unwrap());
-		// take a mutable pointer to the buffer
-		self.ptr = buf.as_mut_ptr();
-		// prevent the buffer from being deallocated when it goes out of scope
-		mem::forget(buf);
-		
-		// clear dumps
-		for i in 0..self.block_number {
-			unsafe { *(self.ptr).add(i.try_into().unwrap()) &= 0; }
-		}
-
-		// Set bits block by block and avoid unnecesary assignments
-		// helper_var1 is used as a helper to assign block to the new Core.
-		helper_var1 = 0;
-
-		// assign index block by block
-		for i in index.to_le_bytes().iter().rev() {
-			if *i == 0 {
-				continue;
-			}
-			unsafe { *(self.ptr).add(helper_var1.try_into().unwrap()) = *i as u8; }
-			helper_var1 += 1;
-		}
+		// assign index block by block, last block_number bytes of the index are used
+		let bytes = index.to_be_bytes();
+		let block_number = self.block_number;
+		self.get_blocks_mut().copy_from_slice(&bytes[bytes.len() - block_number..]);
 	}
 
 	/// This function returns bit count that is being used in bit representation of the Core.
@@ -361,220 +389,422 @@ impl Core {
 		self.block_number * SIZE_PER_BLOCK - self.start_index
 	}
 
-	/// This function the label of the Core (bit representation) in bits.
+	/// This function prints the label of the Core (bit representation) in bits to stdout.
+	/// Prefer `to_bit_string` or `Display` outside of debugging.
 	/// 
 	/// # Arguments
-	/// * `self` - The core itself is required to ptr, get block_number and start_index
+	/// * `self` - The core itself is required to get blocks, block_number and start_index
 	///
 	#[inline(always)]
 	#[allow(dead_code)]
 	pub fn show(&self) {
-		let values = unsafe { std::slice::from_raw_parts(self.ptr, self.block_number as usize) };
-		print!("0b");
+		print!("0b{} ", self.to_bit_string());
+	}
+
+	/// This function returns the label of the Core (bit representation) as a string of bits,
+	/// starting from the most significant bit and without padding, e.g. "0100011" for a 7-bit label.
+	/// 
+	/// # Arguments
+	/// * `self` - The core itself is required to get blocks, block_number and start_index
+	///
+	#[allow(dead_code)]
+	pub fn to_bit_string(&self) -> String {
+		let values = self.get_blocks();
+		let mut bits = String::with_capacity(self.get_bit_count());
 		for index in self.start_index..SIZE_PER_BLOCK {
-			print!("{}", ( values[0] >> ( SIZE_PER_BLOCK - index - 1 ) ) % 2  );
+			bits.push(if ( values[0] >> ( SIZE_PER_BLOCK - index - 1 ) ) % 2 == 1 { '1' } else { '0' });
 		}
 		for value in values[1..].iter() {
-			print!("{:08b}", value);
+			bits.push_str(&format!("{:08b}", value));
 		}
-		print!(" ");
+		bits
 	}
 
-	/// This function returns the blocks of the ptr as slice.
+	/// This function returns the blocks as slice.
 	/// 
 	/// # Arguments
-	/// * `self` - The core itself is required to ptr and get block_number.
+	/// * `self` - The core itself is required to get blocks and block_number.
 	///
 	#[inline(always)]
 	#[allow(dead_code)]
 	pub fn get_blocks(&self) -> &[u8] {
-		let values = unsafe { std::slice::from_raw_parts(self.ptr, self.block_number as usize) };
-		return values;
+		match &self.blocks {
+			Blocks::Inline(blocks) => &blocks[..self.block_number],
+			Blocks::Heap(blocks) => blocks,
+		}
+	}
+
+	/// This function returns the blocks as mutable slice.
+	/// 
+	/// # Arguments
+	/// * `self` - The core itself is required to get blocks and block_number.
+	///
+	#[inline(always)]
+	fn get_blocks_mut(&mut self) -> &mut [u8] {
+		match &mut self.blocks {
+			Blocks::Inline(blocks) => &mut blocks[..self.block_number],
+			Blocks::Heap(blocks) => blocks,
+		}
+	}
+
+	/// This function decodes the label of a level-1 Core back to the characters of the substring.
+	/// Each label is read with the bit size of the alphabet and converted with its characters, hence
+	/// it only applies to the Cores created by `from_str`, `from_u8` or `from_char` with the same alphabet.
+	/// Core does not know its level, so the caller must pass a level-1 Core; `String::get_sequences` checks
+	/// the level of the String instead. None is returned if the bit count of the Core does not match a substring
+	/// of its length, which is the case for most, but not all, of the Cores of higher levels, so decoding a Core
+	/// of a higher level might give characters that are not its substring.
+	/// 
+	/// # Arguments
+	/// * `self` - The core itself is required to get blocks, block_number and start_index.
+	/// * `alphabet` - alphabet encoding used while creating the Core.
+	///
+	#[allow(dead_code)]
+	pub fn decode(&self, alphabet: &Alphabet) -> Option<Vec<u8>> {
+
+		if self.end - self.start == 0 || self.get_bit_count() != ( self.end - self.start ) * alphabet.dict_bit_size {
+			return None;
+		}
+
+		let blocks = self.get_blocks();
+		let mut sequence: Vec<u8> = Vec::with_capacity(self.end - self.start);
+		let mut index: usize = self.start_index;
+
+		while index < self.block_number * SIZE_PER_BLOCK {
+			let mut label: usize = 0;
+			for _ in 0..alphabet.dict_bit_size {
+				label = ( label << 1 ) | ( ( blocks[ index / SIZE_PER_BLOCK ] >> ( SIZE_PER_BLOCK - index % SIZE_PER_BLOCK - 1 ) ) & 1 ) as usize;
+				index += 1;
+			}
+			sequence.push(alphabet.characters[label] as u8);
+		}
+
+		Some(sequence)
 	}
 
 	/// This function returns bit representation as u64.
 	/// If the label has more than 64 bits, this function will return 64 bits from right.
 	/// 
 	/// # Arguments
-	/// * `self` - The core itself is required to ptr and get block_number.
+	/// * `self` - The core itself is required to get blocks.
 	///
 	#[inline(always)]
 	#[allow(dead_code)]
 	pub fn encode(&self) -> u64 {
-		let values = unsafe { std::slice::from_raw_parts(self.ptr, self.block_number as usize) };
 		let mut encoding: u64 = 0;
-		for (index, value) in values.iter().rev().enumerate().filter(|&(i, _x)| i < 4){
-			encoding |= (*value as u64) << (index * 8);
+		for (index, value) in self.get_blocks().iter().rev().take(8).enumerate() {
+			encoding |= (*value as u64) << (index * SIZE_PER_BLOCK);
 		}
-		return encoding;
+		encoding
 	}
-}
-
 
-impl Drop for Core {
-	/// Deconstructor of Core struct is needed as ptr is allocated in heap.
+	/// This function returns bit representation as u128.
+	/// If the label has more than 128 bits, this function will return 128 bits from right.
 	/// 
 	/// # Arguments
-	/// * `self` - The core itself is required to ptr and get block_number.
+	/// * `self` - The core itself is required to get blocks.
 	///
-	fn drop(&mut self) {
-		unsafe {
-			Vec::from_raw_parts(self.ptr, self.block_number as usize, self.block_number as usize);
+	#[inline(always)]
+	#[allow(dead_code)]
+	pub fn encode_u128(&self) -> u128 {
+		let mut encoding: u128 = 0;
+		for (index, value) in self.get_blocks().iter().rev().take(16).enumerate() {
+			encoding |= (*value as u128) << (index * SIZE_PER_BLOCK);
 		}
+		encoding
 	}
-}
 
+	/// This function returns a 64-bit hash of the whole label, regardless of its length.
+	/// Cores with the same label have the same hash, as it is consistent with the equality.
+	/// The hash is only meant to be used within the process, as it is not guaranteed to be
+	/// the same across releases.
+	/// 
+	/// # Arguments
+	/// * `self` - The core itself is required to get blocks and start_index.
+	///
+	#[inline(always)]
+	#[allow(dead_code)]
+	pub fn encode_hash(&self) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		self.hash(&mut hasher);
+		hasher.finish()
+	}
 
-impl PartialEq for Core {
-	/// Equal-to operator (==) is overloaded for Core comparison.
+	/// This function returns a stable, platform-independent fingerprint of the label.
+	/// Unlike `encode_hash`, the values are guaranteed to be the same across machines and releases
+	/// for the same `FINGERPRINT_VERSION`, hence they can be persisted and compared later.
+	/// Positions of the Core are not part of the fingerprint.
+	///
+	/// Version 1 is computed as follows:
+	///     1 - The label is serialized as its bit count (u64, little-endian), followed by the
+	///         label value in big-endian with ceil(bit count / 8) bytes, left-padded with zeros.
+	///     2 - The bytes are hashed with 64-bit FNV-1a, whose offset basis is xored with
+	///         the splitmix64 finalizer of `seed ^ 0x9e3779b97f4a7c15`.
+	///     3 - The splitmix64 finalizer is applied to the hash.
 	/// 
 	/// # Arguments
-	/// * `self` - Core that will be used as lhs.
-	/// * `other` - Core that will be used as rhs
+	/// * `self` - The core itself is required to get blocks and bit count.
+	/// * `seed` - seed of the hash, fingerprints with different seeds are independent.
 	///
-	fn eq(&self, other: &Self) -> bool {
+	#[allow(dead_code)]
+	pub fn fingerprint(&self, seed: u64) -> u64 {
+		let bit_count = self.get_bit_count();
+		let blocks = self.get_blocks();
 
-		if self.block_number != other.block_number {
-			return false;
-		}
+		// blocks might have more bytes than the serialized label, so leading ones are skipped
+		let label = &blocks[blocks.len() - ( bit_count - 1 ) / 8 - 1..];
 
-		if self.start_index != other.start_index {
-			return false;
+		let mut hash = fingerprint_basis(bit_count, seed);
+		for byte in label {
+			hash = fnv(hash, *byte);
 		}
 
-		for (self_block, other_block) in self.get_blocks().iter().zip(other.get_blocks()) {
-			if self_block != other_block {
-				return false;
-			}
-		}
-		return true;
+		mix64(hash)
 	}
-}
 
+	/// This function serializes the Core to compact bytes, which can be read back with `from_bytes`.
+	/// The format is: format version (`SERIALIZATION_VERSION`), start, length (end - start) and bit count
+	/// as varints, followed by the label in big-endian with ceil(bit count / 8) bytes, left-padded with zeros.
+	/// 
+	/// # Arguments
+	/// * `self` - The core itself is required to get positions and label.
+	///
+	#[allow(dead_code)]
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes: Vec<u8> = vec![SERIALIZATION_VERSION];
+		write_varint(self.start as u64, &mut bytes);
+		write_varint(( self.end - self.start ) as u64, &mut bytes);
+		self.write_label(&mut bytes);
+		bytes
+	}
 
-impl PartialOrd for Core {
+	/// Constructor of Core from bytes written by `to_bytes`.
+	/// 
+	/// # Arguments
+	/// * `bytes` - serialized Core, without any trailing bytes.
+	///
+	#[allow(dead_code)]
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+		let mut position: usize = 0;
+		read_version(bytes, &mut position)?;
+		let start = read_usize(bytes, &mut position)?;
+		let end = start.checked_add(read_usize(bytes, &mut position)?).ok_or(Error::Corrupted { reason: "position overflows" })?;
+		let core = Self::read_label(bytes, &mut position, start, end)?;
+		if position != bytes.len() {
+			return Err(Error::Corrupted { reason: "trailing bytes" });
+		}
+		Ok(core)
+	}
 
-	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		Some(self.cmp(other))
+	/// This function appends the bit count of the label as varint and the label itself, see `to_bytes`.
+	/// 
+	/// # Arguments
+	/// * `self` - The core itself is required to get label.
+	/// * `bytes` - buffer that the label is appended to.
+	///
+	pub(crate) fn write_label(&self, bytes: &mut Vec<u8>) {
+		let bit_count = self.get_bit_count();
+		let blocks = self.get_blocks();
+		write_varint(bit_count as u64, bytes);
+		bytes.extend_from_slice(&blocks[blocks.len() - ( bit_count - 1 ) / 8 - 1..]);
 	}
 
-	/// Less-than operator (<) is overloaded for Core comparison.
+	/// Constructor of Core from the label written by `write_label` and given positions.
 	/// 
 	/// # Arguments
-	/// * `self` - Core that will be used as lhs.
-	/// * `other` - Core that will be used as rhs
+	/// * `bytes` - buffer that the label is read from.
+	/// * `position` - position of the label in the buffer, it is moved after the label.
+	/// * `start` - start position of the Core.
+	/// * `end` - end position of the Core.
 	///
-	fn lt(&self, other: &Self) -> bool { 
-		if self.block_number < other.block_number {
-			return true;
-		} else if self.block_number > other.block_number {
-			return false;
+	pub(crate) fn read_label(bytes: &[u8], position: &mut usize, start: usize, end: usize) -> Result<Self> {
+		let bit_count = read_usize(bytes, position)?;
+		if bit_count == 0 || start >= end {
+			return Err(Error::Corrupted { reason: "empty core" });
 		}
 
-		if self.start_index > other.start_index {
-			return true;
-		} else if self.start_index < other.start_index {
-			return false;
-		}
+		// bit count is checked against the remaining bytes before it is used, as it is not trusted
+		let block_number = ( bit_count - 1 ) / SIZE_PER_BLOCK + 1;
+		let label = bytes.get(*position..).and_then(|rest| rest.get(..block_number)).ok_or(Error::Corrupted { reason: "unexpected end of bytes" })?;
+		let start_index = block_number * SIZE_PER_BLOCK - bit_count;
+		*position += block_number;
 
-		for (self_block, other_block) in self.get_blocks().iter().zip(other.get_blocks()) {
-			if self_block < other_block {
-				return true;
-			} else if self_block > other_block {
-				return false;
-			}
+		// padding bits should be cleared, otherwise equal labels would differ
+		if start_index > 0 && label[0] >> ( SIZE_PER_BLOCK - start_index ) != 0 {
+			return Err(Error::Corrupted { reason: "padding bits of the label are set" });
 		}
 
-		return false;
+		let mut core = Core {
+			blocks: Blocks::new(block_number),
+			block_number,
+			start_index,
+			start,
+			end
+		};
+		core.get_blocks_mut().copy_from_slice(label);
+
+		Ok(core)
+	}
+}
+
+
+/// This function computes the fingerprint of the characters of a substring, as `fingerprint` of the Core
+/// that `from_u8` creates from them, without creating the Core. Labels are packed into the bytes
+/// of the label as they are read, hence it does not allocate whatever the length of the substring is.
+///
+/// # Arguments
+/// * `labels` - labels of the characters of the substring.
+/// * `length` - number of characters in the substring.
+/// * `bit_size` - number of bits used to store each label.
+/// * `seed` - seed of the hash, fingerprints with different seeds are independent.
+///
+pub(crate) fn fingerprint_labels<I: Iterator<Item = u8>>(labels: I, length: usize, bit_size: usize, seed: u64) -> u64 {
+	let bit_count = length * bit_size;
+	let mut hash = fingerprint_basis(bit_count, seed);
+
+	// label is left-padded with zeros to whole bytes, then the bits are written starting from the most significant one
+	let mut bits: u64 = 0;
+	let mut pending: usize = ( 8 - bit_count % 8 ) % 8;
+
+	for label in labels {
+		bits = ( bits << bit_size ) | label as u64;
+		pending += bit_size;
+		while pending >= 8 {
+			pending -= 8;
+			hash = fnv(hash, ( bits >> pending ) as u8);
+		}
 	}
 
-	/// Less-than-or-equal-to operator (<=) is overloaded for Core comparison.
+	mix64(hash)
+}
+
+
+/// This function returns the FNV-1a hash state of a fingerprint after its bit count is hashed.
+///
+/// # Arguments
+/// * `bit_count` - number of bits in the label.
+/// * `seed` - seed of the hash.
+///
+#[inline(always)]
+fn fingerprint_basis(bit_count: usize, seed: u64) -> u64 {
+	( bit_count as u64 ).to_le_bytes().iter().fold(0xcbf29ce484222325 ^ mix64(seed ^ 0x9e3779b97f4a7c15), |hash, byte| fnv(hash, *byte))
+}
+
+
+/// Single step of FNV-1a, used to hash the bytes of the fingerprint.
+///
+/// # Arguments
+/// * `hash` - current hash state.
+/// * `byte` - the byte to be hashed.
+///
+#[inline(always)]
+fn fnv(hash: u64, byte: u8) -> u64 {
+	( hash ^ byte as u64 ).wrapping_mul(0x100000001b3)
+}
+
+
+/// Finalizer of splitmix64, used to mix the bits of the fingerprint.
+///
+/// # Arguments
+/// * `value` - the value to be mixed.
+///
+#[inline(always)]
+fn mix64(mut value: u64) -> u64 {
+	value = ( value ^ ( value >> 30 ) ).wrapping_mul(0xbf58476d1ce4e5b9);
+	value = ( value ^ ( value >> 27 ) ).wrapping_mul(0x94d049bb133111eb);
+	value ^ ( value >> 31 )
+}
+
+
+impl fmt::Display for Core {
+	/// Core is displayed with its positions, bit count and label, e.g. "[1, 6) 10 bits 0b1000101101".
 	/// 
 	/// # Arguments
-	/// * `self` - Core that will be used as lhs.
-	/// * `other` - Core that will be used as rhs
+	/// * `self` - Core that will be displayed.
+	/// * `f` - Formatter that is used.
 	///
-	fn le(&self, other: &Self) -> bool {
-		if self.block_number < other.block_number {
-			return true;
-		} else if self.block_number > other.block_number {
-			return false;
-		}
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "[{}, {}) {} bits 0b{}", self.start, self.end, self.get_bit_count(), self.to_bit_string())
+	}
+}
 
-		if self.start_index > other.start_index {
-			return true;
-		} else if self.start_index < other.start_index {
-			return false;
-		}
 
-		for (self_block, other_block) in self.get_blocks().iter().zip(other.get_blocks()) {
-			if self_block < other_block {
-				return true;
-			} else if self_block > other_block {
-				return false;
-			}
-		}
+impl fmt::Debug for Core {
 
-		return true;
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Core")
+			.field("start", &self.start)
+			.field("end", &self.end)
+			.field("bit_count", &self.get_bit_count())
+			.field("label", &format_args!("0b{}", self.to_bit_string()))
+			.finish()
 	}
+}
 
-	/// Greater-than operator (<) is overloaded for Core comparison.
+
+impl PartialEq for Core {
+	/// Equal-to operator (==) is overloaded for Core comparison.
 	/// 
 	/// # Arguments
 	/// * `self` - Core that will be used as lhs.
 	/// * `other` - Core that will be used as rhs
 	///
-	fn gt(&self, other: &Self) -> bool {
-		if self.block_number > other.block_number {
-			return true;
-		} else if self.block_number < other.block_number {
+	fn eq(&self, other: &Self) -> bool {
+
+		if self.block_number != other.block_number {
 			return false;
 		}
 
-		if self.start_index < other.start_index {
-			return true;
-		} else if self.start_index > other.start_index {
+		if self.start_index != other.start_index {
 			return false;
 		}
 
 		for (self_block, other_block) in self.get_blocks().iter().zip(other.get_blocks()) {
-			if self_block > other_block {
-				return true;
-			} else if self_block < other_block {
+			if self_block != other_block {
 				return false;
 			}
 		}
-
-		return false;
+		true
 	}
+}
+
+
+impl Eq for Core {}
+
 
-	/// Greater-than-or-equal-to operator (>=) is overloaded for Core comparison.
+impl Hash for Core {
+	/// Hash of the Core is consistent with the equality, hence only the label is used,
+	/// and Cores with same label at different positions have the same hash.
 	/// 
 	/// # Arguments
-	/// * `self` - Core that will be used as lhs.
-	/// * `other` - Core that will be used as rhs
+	/// * `self` - Core that will be hashed.
+	/// * `state` - Hasher that is used.
 	///
-	fn ge(&self, other: &Self) -> bool {
-		if self.block_number > other.block_number {
-			return true;
-		} else if self.block_number < other.block_number {
-			return false;
-		}
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.start_index.hash(state);
+		self.get_blocks().hash(state);
+	}
+}
 
-		if self.start_index < other.start_index {
-			return true;
-		} else if self.start_index > other.start_index {
-			return false;
-		}
 
-		for (self_block, other_block) in self.get_blocks().iter().zip(other.get_blocks()) {
-			if self_block > other_block {
-				return true;
-			} else if self_block < other_block {
-				return false;
-			}
-		}
+impl PartialOrd for Core {
+
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
 
-		return true;
+
+impl Ord for Core {
+	/// Cores are ordered by their labels (positions are not compared), consistent with the equality.
+	/// A label with less bits is smaller, labels with the same bit count are compared as numbers.
+	/// 
+	/// # Arguments
+	/// * `self` - Core that will be used as lhs.
+	/// * `other` - Core that will be used as rhs
+	///
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.get_bit_count().cmp(&other.get_bit_count())
+			.then_with(|| self.get_blocks().cmp(other.get_blocks()))
 	}
-}
\ No newline at end of file
+}
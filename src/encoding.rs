@@ -1,22 +1,276 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::cmp;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead};
 use std::path::Path;
-use crate::statics::LABELS;
-use crate::statics::CHARACTERS;
-use crate::statics::DICT_BIT_SIZE;
-use crate::statics::ENCODING_INIT;
+use std::path::PathBuf;
 use crate::statics::LOG_INIT;
+use crate::statics::{LEARNING_SAMPLE_SIZE, LEARNING_PERMUTATION_LIMIT, LEARNING_EVALUATION_LIMIT};
+use crate::error::{Error, Result};
 use std::env;
-use std::process;
-use log::{info, error};
+use log::info;
+
+
+/// Policy that defines how the characters that are not part of the alphabet are handled while parsing.
+/// Such characters are called ambiguous, e.g. IUPAC ambiguity codes (R, Y, ...) and N in DNA alphabet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AmbiguityPolicy {
+	/// Sequence is split into segments at ambiguous characters and each segment is parsed on its own.
+	/// Positions of the cores are still given with respect to the whole sequence.
+	#[default]
+	Split,
+	/// Sequence is parsed as a whole but windows and cores that contain ambiguous characters are skipped.
+	Skip,
+	/// Ambiguous characters are parsed as if they were the given character. The character should be
+	/// part of the alphabet, otherwise `Split` is used instead.
+	Map(char),
+}
+
+
+/// Alphabet encoding used by the lcp algorithm. Each character is mapped to a label and
+/// the label is stored with `dict_bit_size` bits in the cores. Since alphabet is an
+/// ordinary value, different alphabets can be used at the same time, even from different
+/// threads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+	/// Label of each character, indexed by the character itself (byte value).
+	/// Characters that are not part of the alphabet have label -1.
+	pub labels: [i32; 256],
+	/// Character of each label, indexed by the label. It is used to decode labels back.
+	pub characters: [char; 256],
+	/// Number of bits required to store a single label.
+	pub dict_bit_size: usize,
+	/// Policy used for the characters that are not part of the alphabet.
+	pub ambiguity: AmbiguityPolicy,
+	/// Complement of each character (byte value), e.g. for the reverse complement of DNA. Characters
+	/// without complement pair are their own complement. None if complements are not defined.
+	pub complement: Option<[u8; 256]>,
+}
+
+
+impl Default for Alphabet {
+	/// Default alphabet is the DNA alphabet described in `init_coefficients_default`.
+	///
+	fn default() -> Self {
+		Self::dna()
+	}
+}
+
+
+impl Alphabet {
+
+	/// Constructor of an empty Alphabet. All characters are invalid (-1).
+	///
+	fn empty() -> Self {
+		Alphabet {
+			labels: [-1; 256],
+			characters: [126 as char; 256],
+			dict_bit_size: 0,
+			ambiguity: AmbiguityPolicy::default(),
+			complement: None,
+		}
+	}
+
+	/// Constructor of DNA Alphabet, see `init_coefficients_default`.
+	///
+	fn dna() -> Self {
+
+		let mut alphabet = Alphabet::empty();
+
+		// init coefficients A/a=0, T/t=3, G/g=2, C/c=1
+
+		alphabet.labels['A' as usize] = 0; alphabet.labels['a' as usize] = 0;
+		alphabet.labels['T' as usize] = 3; alphabet.labels['t' as usize] = 3;
+		alphabet.labels['G' as usize] = 2; alphabet.labels['g' as usize] = 2;
+		alphabet.labels['C' as usize] = 1; alphabet.labels['c' as usize] = 1;
+
+		alphabet.characters[0] = 'A';
+		alphabet.characters[1] = 'C';
+		alphabet.characters[2] = 'G';
+		alphabet.characters[3] = 'T';
+
+		alphabet.dict_bit_size = 2;
+
+		alphabet.with_complement(&[('A', 'T'), ('C', 'G'), ('a', 't'), ('c', 'g')]).unwrap()
+	}
+
+	/// This function returns the alphabet with given complement pairs. Each pair is complement
+	/// of each other, and characters that are not given are their own complement.
+	///
+	/// # Arguments
+	///
+	/// * `pairs` - complement pairs, both characters should be part of the alphabet.
+	///
+	pub fn with_complement(mut self, pairs: &[(char, char)]) -> Result<Alphabet> {
+
+		let mut complement = [0; 256];
+		for (ch, value) in complement.iter_mut().enumerate() {
+			*value = ch as u8;
+		}
+
+		for &(first, second) in pairs {
+			for ch in [first, second] {
+				if ch as u32 > 255 || self.is_ambiguous(ch as u8) {
+					return Err(Error::InvalidComplement { character: ch });
+				}
+			}
+			complement[first as usize] = second as u8;
+			complement[second as usize] = first as u8;
+		}
+
+		self.complement = Some(complement);
+		Ok(self)
+	}
+
+	/// This function returns a copy of the alphabet whose labels are shuffled with a permutation generated
+	/// from given seed. The same seed always gives the same permutation. Aliases keep sharing their label,
+	/// and number of bits used for labels does not change.
+	///
+	/// # Arguments
+	///
+	/// * `seed` - seed of the permutation.
+	///
+	pub fn permute(&self, seed: u64) -> Alphabet {
+
+		let values: Vec<usize> = (0..self.characters.len()).filter(|value| self.labels.contains(&(*value as i32))).collect();
+		let mut permutation: Vec<usize> = values.clone();
+		let mut state = seed;
+
+		// Fisher-Yates shuffle
+		for index in (1..permutation.len()).rev() {
+			let other = ( splitmix64(&mut state) % ( index as u64 + 1 ) ) as usize;
+			permutation.swap(index, other);
+		}
+
+		let mut alphabet = self.clone();
+		let mut mapping = [-1; 256];
+
+		for (value, permuted) in values.iter().zip(permutation.iter()) {
+			alphabet.characters[*permuted] = self.characters[*value];
+			mapping[*value] = *permuted as i32;
+		}
+		for label in alphabet.labels.iter_mut().filter(|label| **label >= 0) {
+			*label = mapping[*label as usize];
+		}
+
+		alphabet
+	}
+
+	/// This function returns the label of given character, or `None` if the character is not part of the alphabet.
+	///
+	/// # Arguments
+	///
+	/// * `ch` - character, given as byte, whose label is requested.
+	///
+	#[inline(always)]
+	pub fn label(&self, ch: u8) -> Option<i32> {
+		match self.labels.get(ch as usize) {
+			Some(&label) if label >= 0 => Some(label),
+			_ => None,
+		}
+	}
+
+	/// This function returns true if given character is not part of the alphabet.
+	///
+	/// # Arguments
+	///
+	/// * `ch` - character, given as byte, that will be checked.
+	///
+	#[inline(always)]
+	pub fn is_ambiguous(&self, ch: u8) -> bool {
+		self.label(ch).is_none()
+	}
+}
+
+
+/// Alphabet encoding used to parse text over Unicode scalar values. Unlike `Alphabet`, it is built
+/// dynamically from the characters that are seen, hence it is not limited to a single byte.
+/// Labels are given in the increasing order of code points, so comparison of labels is the same
+/// as comparison of characters.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct TextAlphabet {
+	/// Label of each character of the alphabet.
+	pub labels: HashMap<char, u32>,
+	/// Character of each label, indexed by the label. It is used to decode labels back.
+	pub characters: Vec<char>,
+	/// Number of bits required to store a single label. It is at least 1.
+	pub dict_bit_size: usize,
+}
+
+
+impl TextAlphabet {
+
+	/// Constructor of TextAlphabet from given characters. Duplicate characters are ignored.
+	///
+	/// # Arguments
+	///
+	/// * `chars` - characters of the alphabet.
+	///
+	pub fn from_chars<I: IntoIterator<Item = char>>(chars: I) -> Self {
+
+		let mut characters: Vec<char> = chars.into_iter().collect();
+		characters.sort_unstable();
+		characters.dedup();
+
+		let labels: HashMap<char, u32> = characters.iter().enumerate().map(|(label, ch)| (*ch, label as u32)).collect();
+
+		let mut max_value = characters.len().saturating_sub(1);
+		let mut bit_count = 0;
+
+		while max_value > 0 {
+			bit_count += 1;
+			max_value /= 2;
+		}
+
+		TextAlphabet {
+			labels,
+			characters,
+			dict_bit_size: cmp::max(bit_count, 1),
+		}
+	}
+
+	/// Constructor of TextAlphabet with the characters of given text.
+	///
+	/// # Arguments
+	///
+	/// * `text` - text whose characters compose the alphabet.
+	///
+	pub fn from_text(text: &str) -> Self {
+		Self::from_chars(text.chars())
+	}
+
+	/// This function returns the label of given character, or `None` if the character is not part of the alphabet.
+	///
+	/// # Arguments
+	///
+	/// * `ch` - character whose label is requested.
+	///
+	#[inline(always)]
+	pub fn label(&self, ch: char) -> Option<u32> {
+		self.labels.get(&ch).copied()
+	}
+}
+
+
+/// SplitMix64 pseudo random number generator. It is used to generate permutations from seeds.
+///
+/// # Arguments
+///
+/// * `state` - state of the generator, updated at each call.
+///
+fn splitmix64(state: &mut u64) -> u64 {
+	*state = state.wrapping_add(0x9E3779B97F4A7C15);
+	let mut z = *state;
+	z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+	z ^ (z >> 31)
+}
 
 
 /// This function creates buffer that will help to read file
-/// 
+///
 /// # Arguments
-/// 
+///
 /// * `filename` - filename that will be read.
 ///
 #[allow(dead_code)]
@@ -27,171 +281,606 @@ pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
 
 
 /// This function initalizes logging. `log` dependancy is used for logging. Specifically info and error ones.
-/// 
+/// Logging is initialized only once per process.
+///
 /// # Arguments
-/// 
+///
 /// * `verbose` - this parameter is used to determine the logging functionality of the program to be used or not. If True is passed, then logging is on, and if False then logging off.
 ///
 #[allow(dead_code)]
 pub fn init_logging(verbose: bool) {
 
-	unsafe {
-		if LOG_INIT == true {
-			return;
-		}
-
-		if verbose == true {
+	LOG_INIT.call_once(|| {
+		if verbose {
 			env::set_var("RUST_LOG", "main");
 			env_logger::init();
 		} else {
 			env::remove_var("RUST_LOG");
 		}
-		LOG_INIT = true;
-	}
+	});
 }
 
 
 /// This function prints summary of alphabet encoding. It will only print characters and values of those which were initialized.
 ///
+/// # Arguments
+///
+/// * `alphabet` - alphabet that will be summarized.
+///
 #[allow(dead_code)]
-pub fn encoding_summary() {
+pub fn encoding_summary(alphabet: &Alphabet) {
 	info!("# Alphabet encoding summary");
-	info!("# Coefficients: {:?}", unsafe { LABELS.into_iter().enumerate().filter(|&(_i, v)| v != -1).map(|(i, e)| ((i as u8) as char, e)).collect::<Vec<_>>() });
-	info!("# Dictionary bit size: {}", unsafe { DICT_BIT_SIZE} );
+	info!("# Coefficients: {:?}", alphabet.labels.iter().enumerate().filter(|&(_i, v)| *v != -1).map(|(i, e)| ((i as u8) as char, *e)).collect::<Vec<_>>());
+	info!("# Dictionary bit size: {}", alphabet.dict_bit_size);
+	info!("# Ambiguity policy: {:?}", alphabet.ambiguity);
 }
 
 
-/// This function initalizes alphabet encoding with default values. Default values are as follows:
-/// 
+/// This function creates alphabet encoding with default values. Default values are as follows:
+///
 /// A/a=0, T/t=3, G/g=2, C/c=1
-/// 
+///
 /// Since this algorithm is designed to be used in bioinformatics, characters are selected to be base pairs.
-/// 
+///
 /// Invalid value is set to -1.
-/// 
+///
 /// # Arguments
-/// 
+///
 /// * `verbose` - this parameter is used determine whether to use logging or not.
-/// 
+///
 #[allow(dead_code)]
-pub fn init_coefficients_default(verbose: bool) {
+pub fn init_coefficients_default(verbose: bool) -> Result<Alphabet> {
 
-	unsafe {
-		init_logging(verbose);
+	init_logging(verbose);
 
-		LABELS = [-1; 128];
-		CHARACTERS = [126 as char; 128];
-	
-		// init coefficients A/a=0, T/t=3, G/g=2, C/c=1
-		
-		LABELS['A' as usize] = 0; LABELS['a' as usize] = 0;
-		LABELS['T' as usize] = 3; LABELS['t' as usize] = 3;
-		LABELS['G' as usize] = 2; LABELS['g' as usize] = 2;
-		LABELS['C' as usize] = 1; LABELS['c' as usize] = 1;
-		
-		CHARACTERS[0] = 'A';
-		CHARACTERS[1] = 'C';
-		CHARACTERS[2] = 'G';
-		CHARACTERS[3] = 'T';
-	
-		DICT_BIT_SIZE = 2;
-	
-		if DICT_BIT_SIZE > 6 {
-			error!("Dictionary bit size is : {}. This cannot be greater than 6. Please provide labels with values less then 64.", DICT_BIT_SIZE);
-			process::exit(1);
-		}
-	
-		encoding_summary(); 
-	
-		ENCODING_INIT = true;
-	}
-
-}
-
-
-/// This function initalizes alphabet encoding with given values in map. 
-/// For the characters that value is not provided, it is set to -1. 
-/// Also, negatıve values are not accepted.
-/// 
-/// # Arguments
-/// 
+	let alphabet = Alphabet::dna();
+
+	encoding_summary(&alphabet);
+
+	Ok(alphabet)
+}
+
+
+/// This function creates alphabet encoding for proteins. The 20 standard amino acids are labeled
+/// in the alphabetical order of their one letter codes, followed by the unknown amino acid and stop:
+///
+/// A/a=0, C/c=1, D/d=2, E/e=3, F/f=4, G/g=5, H/h=6, I/i=7, K/k=8, L/l=9,
+/// M/m=10, N/n=11, P/p=12, Q/q=13, R/r=14, S/s=15, T/t=16, V/v=17, W/w=18, Y/y=19,
+/// X/x=20, *=21
+///
+/// Hence, labels are stored with 5 bits.
+///
+/// Invalid value is set to -1.
+///
+/// # Arguments
+///
+/// * `verbose` - this parameter is used determine whether to use logging or not.
+///
+#[allow(dead_code)]
+pub fn init_coefficients_protein(verbose: bool) -> Result<Alphabet> {
+
+	init_logging(verbose);
+
+	let mut alphabet = Alphabet::empty();
+
+	for (label, ch) in "ACDEFGHIKLMNPQRSTVWYX*".chars().enumerate() {
+		alphabet.labels[ch as usize] = label as i32;
+		alphabet.labels[ch.to_ascii_lowercase() as usize] = label as i32;
+		alphabet.characters[label] = ch;
+	}
+
+	alphabet.dict_bit_size = 5;
+
+	encoding_summary(&alphabet);
+
+	Ok(alphabet)
+}
+
+
+/// This function creates alphabet encoding for arbitrary binary data. Each byte is labeled with its own value:
+///
+/// 0x00=0, 0x01=1, ..., 0xff=255
+///
+/// Hence, labels are stored with 8 bits and there is no invalid character. Characters of the labels
+/// are the Unicode characters with the same code point (Latin-1).
+///
+/// # Arguments
+///
+/// * `verbose` - this parameter is used determine whether to use logging or not.
+///
+#[allow(dead_code)]
+pub fn init_coefficients_bytes(verbose: bool) -> Result<Alphabet> {
+
+	init_logging(verbose);
+
+	let mut alphabet = Alphabet::empty();
+
+	for value in 0..=u8::MAX {
+		alphabet.labels[value as usize] = value as i32;
+		alphabet.characters[value as usize] = value as char;
+	}
+
+	alphabet.dict_bit_size = 8;
+
+	encoding_summary(&alphabet);
+
+	Ok(alphabet)
+}
+
+
+/// This function checks whether given character and value can be a part of an alphabet.
+/// Negatıve values, values greater than 255 and characters that do not fit in a single byte are not accepted.
+///
+/// # Arguments
+///
+/// * `key` - character that is intended to be encoded.
+/// * `value` - value that is intended to be used as label of the character.
+///
+fn check_coefficient(key: char, value: i32) -> Result<()> {
+
+	if !(0..=u8::MAX as i32).contains(&value) {
+		return Err(Error::InvalidLabel { character: key, value });
+	}
+	if u8::try_from(key).is_err() {
+		return Err(Error::InvalidCharacter { character: key });
+	}
+
+	Ok(())
+}
+
+/// This function creates alphabet encoding with given characters and values. Characters are processed in the
+/// given order, and the first character that is given for a value is used to decode that value back.
+/// Values are checked with `check_coefficient`.
+///
+/// # Arguments
+///
+/// * `coefficients` - characters and the values that are intended to be encoded.
+///
+fn from_coefficients<I: IntoIterator<Item = (char, i32)>>(coefficients: I) -> Result<Alphabet> {
+
+	let mut alphabet = Alphabet::empty();
+	let mut decoded = [false; 256];
+	let mut max_value = -1;
+
+	for (key, value) in coefficients {
+
+		check_coefficient(key, value)?;
+
+		alphabet.labels[key as usize] = value;
+		if !decoded[value as usize] {
+			alphabet.characters[value as usize] = key;
+			decoded[value as usize] = true;
+		}
+		max_value = cmp::max(max_value, value);
+	}
+
+	if max_value < 0 {
+		return Err(Error::EmptyAlphabet);
+	}
+
+	let mut bit_count = 0;
+
+	while max_value > 0 {
+		bit_count += 1;
+		max_value /= 2;
+	}
+
+	alphabet.dict_bit_size = cmp::max(bit_count, 1);
+
+	Ok(alphabet)
+}
+
+
+/// This function creates alphabet encoding with given values in map.
+/// For the characters that value is not provided, it is set to -1.
+/// Also, negatıve values, values greater than 255 and characters that do not fit in a single byte are not accepted.
+/// Labels are stored with at least 1 bit.
+///
+/// # Arguments
+///
 /// * `map` - map that contains character and the value that is intended to be encoded.
 /// * `verbose` - this parameter is used determine whether to use logging or not.
-/// 
+///
 #[allow(dead_code)]
-pub fn init_coefficients_map(map: HashMap<char, i32>, verbose: bool) {
+pub fn init_coefficients_map(map: HashMap<char, i32>, verbose: bool) -> Result<Alphabet> {
 
-	unsafe {
-		init_logging(verbose);
+	init_logging(verbose);
 
-		LABELS = [-1; 128];
-		CHARACTERS = [126 as char; 128];
-	
-		// init coefficients A/a=0, T/t=3, G/g=2, C/c=1
-	
-		let mut max_value = 0;
-		
-		for (key, value) in map.into_iter() {
-	
-			if value < 0 {
-				error!("Invalid value ({}) provided for {}", value, key);
-				process::exit(1);
+	let alphabet = from_coefficients(map)?;
+
+	encoding_summary(&alphabet);
+
+	Ok(alphabet)
+}
+
+
+/// Report of the alphabet that is learned from symbol frequencies, see `init_coefficients_frequency`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrequencyReport {
+	/// Number of occurrences of each character in the sample, given in the order of labels.
+	pub frequencies: Vec<(char, usize)>,
+	/// Number of cores per kilobase expected with the learned alphabet. It is measured on the sample.
+	pub cores_per_kb: f64,
+	/// Mean squared distance between the starts of consecutive cores of the sample, see `core_balance`.
+	pub mean_squared_gap: f64,
+	/// Number of label assignments that are evaluated on the sample.
+	pub evaluated: usize,
+}
+
+
+/// This function returns how balanced the cores found in given sample with given alphabet are, together with
+/// the number of cores per kilobase. Balance is measured as the mean squared distance between the starts of
+/// consecutive cores, where the start and the end of the sample count as core starts as well. It is the sum of
+/// the squared mean distance and the variance of the distances, hence it is small only if cores are both dense
+/// and regularly spaced, and it is large if there are long stretches of the sample without any core.
+///
+/// # Arguments
+///
+/// * `sample` - sample of the input.
+/// * `alphabet` - alphabet encoding used to label the characters.
+///
+pub(crate) fn core_balance(sample: &[u8], alphabet: &Alphabet) -> (f64, f64) {
+	let cores = crate::String::from_u8(sample, alphabet).cores;
+
+	let mut previous: usize = 0;
+	let mut sum: f64 = 0.0;
+	for start in cores.iter().map(|core| core.start).chain([sample.len()]) {
+		sum += ( ( start - previous ) as f64 ).powi(2);
+		previous = start;
+	}
+
+	(sum / ( cores.len() + 1 ) as f64, cores.len() as f64 * 1000.0 / sample.len() as f64)
+}
+
+
+/// This function returns the candidate label assignments that are derived from the frequencies. Characters are
+/// given in the order of labels. If there are at most `LEARNING_PERMUTATION_LIMIT` characters, all assignments are
+/// candidates. Otherwise, increasing and decreasing frequency orders, and the orders that place the frequent
+/// characters either to the middle or to the both ends of the labels are the candidates.
+///
+/// # Arguments
+///
+/// * `characters` - characters sorted by decreasing frequency.
+///
+fn candidate_orders(characters: &[u8]) -> Vec<Vec<u8>> {
+
+	if characters.len() <= LEARNING_PERMUTATION_LIMIT {
+		// Heap's algorithm
+		let mut orders: Vec<Vec<u8>> = Vec::new();
+		let mut order = characters.to_vec();
+		let mut counters = vec![0; order.len()];
+		let mut index = 0;
+		orders.push(order.clone());
+		while index < order.len() {
+			if counters[index] < index {
+				order.swap(if index % 2 == 0 { 0 } else { counters[index] }, index);
+				orders.push(order.clone());
+				counters[index] += 1;
+				index = 0;
+			} else {
+				counters[index] = 0;
+				index += 1;
 			}
-			LABELS[key as usize] = value;
-			CHARACTERS[value as usize] = key;
-			max_value = cmp::max(max_value, value);
-		};
-		
-		let mut bit_count = 0;
-		
-		while max_value > 0 {
-			bit_count += 1;
-			max_value = max_value / 2;
 		}
-	
-		DICT_BIT_SIZE = bit_count;
-	
-		if DICT_BIT_SIZE > 6 {
-			error!("Dictionary bit size is : {}. This cannot be >6. Please provide labels with smaller values.", DICT_BIT_SIZE);
-			process::exit(1);
+		return orders;
+	}
+
+	let decreasing = characters.to_vec();
+	let increasing: Vec<u8> = characters.iter().rev().copied().collect();
+
+	// frequent characters in the middle, rare ones at both ends
+	let mut middle: Vec<u8> = Vec::with_capacity(characters.len());
+	for (index, ch) in increasing.iter().enumerate() {
+		if index % 2 == 0 { middle.insert(0, *ch); } else { middle.push(*ch); }
+	}
+	// frequent characters at both ends, rare ones in the middle
+	let mut ends: Vec<u8> = Vec::with_capacity(characters.len());
+	for (index, ch) in decreasing.iter().enumerate() {
+		if index % 2 == 0 { ends.insert(0, *ch); } else { ends.push(*ch); }
+	}
+
+	vec![decreasing, increasing, middle, ends]
+}
+
+
+/// This function learns alphabet encoding from the symbol frequencies of given sample. Since the cores are
+/// found by comparing the labels of neighbouring characters, core density depends on which character gets
+/// which label. Each candidate label assignment (see `candidate_orders`) is evaluated on the sample, then the
+/// best one is improved by swapping neighbouring labels as long as the balance improves. The assignment whose
+/// cores are the most balanced (see `core_balance`) is selected, so that cores are neither sparse nor clustered.
+///
+/// Only the characters seen in the sample are part of the alphabet and at most `LEARNING_SAMPLE_SIZE` characters
+/// of the sample are used for the evaluation. Each evaluation parses the sample, and at most
+/// `LEARNING_EVALUATION_LIMIT` evaluations are done, so learning costs at most as much as parsing 16 MiB;
+/// for large alphabets, swapping is stopped when the limit is reached.
+///
+/// # Arguments
+///
+/// * `sample` - sample of the input.
+/// * `verbose` - this parameter is used determine whether to use logging or not.
+///
+#[allow(dead_code)]
+pub fn init_coefficients_frequency(sample: &[u8], verbose: bool) -> Result<(Alphabet, FrequencyReport)> {
+
+	init_logging(verbose);
+
+	let mut counts = [0usize; 256];
+	for ch in sample {
+		counts[*ch as usize] += 1;
+	}
+
+	let mut characters: Vec<u8> = (0..=u8::MAX).filter(|ch| counts[*ch as usize] > 0).collect();
+	characters.sort_by(|lhs, rhs| counts[*rhs as usize].cmp(&counts[*lhs as usize]).then(lhs.cmp(rhs)));
+
+	let build = |order: &[u8]| from_coefficients(order.iter().enumerate().map(|(label, ch)| (char::from(*ch), label as i32)));
+	let sample = &sample[..cmp::min(sample.len(), LEARNING_SAMPLE_SIZE)];
+
+	let mut evaluated = 0;
+	let mut best: Option<((f64, f64), Vec<u8>)> = None;
+
+	for order in candidate_orders(&characters) {
+		let balance = core_balance(sample, &build(&order)?);
+		evaluated += 1;
+		if best.as_ref().is_none_or(|(best_balance, _)| balance.0 < best_balance.0) {
+			best = Some((balance, order));
+		}
+	}
+
+	let (mut balance, mut order) = best.ok_or(Error::EmptyAlphabet)?;
+
+	if characters.len() > LEARNING_PERMUTATION_LIMIT {
+		let mut improved = true;
+		'passes: while improved {
+			improved = false;
+			for index in 1..order.len() {
+				if evaluated >= LEARNING_EVALUATION_LIMIT { break 'passes; }
+				order.swap(index - 1, index);
+				let candidate = core_balance(sample, &build(&order)?);
+				evaluated += 1;
+				if candidate.0 < balance.0 {
+					balance = candidate;
+					improved = true;
+				} else {
+					order.swap(index - 1, index);
+				}
+			}
 		}
-	
-		encoding_summary(); 
-	
-		ENCODING_INIT = true;
+	}
+
+	let alphabet = build(&order)?;
+	let report = FrequencyReport {
+		frequencies: order.iter().map(|ch| (char::from(*ch), counts[*ch as usize])).collect(),
+		cores_per_kb: balance.1,
+		mean_squared_gap: balance.0,
+		evaluated,
+	};
+
+	encoding_summary(&alphabet);
+	info!("# Expected cores per kilobase: {:.2}", report.cores_per_kb);
+
+	Ok((alphabet, report))
+}
+
+
+/// This function parses a character written in alphabet file. A character is either written as itself,
+/// as `\xHH` where `HH` is its value in hexadecimal, or as `\u{H...}` for any Unicode character.
+///
+/// # Arguments
+///
+/// * `token` - character as written in the file.
+///
+fn parse_character(token: &str) -> Option<char> {
+
+	let mut chars = token.chars();
+
+	match (chars.next(), chars.next()) {
+		(Some(ch), None) => Some(ch),
+		(Some('\\'), Some('x')) if token.len() == 4 => u8::from_str_radix(&token[2..], 16).ok().map(char::from),
+		(Some('\\'), Some('u')) => token[2..].strip_prefix('{').and_then(|rest| rest.strip_suffix('}'))
+			.and_then(|hex| u32::from_str_radix(hex, 16).ok())
+			.and_then(char::from_u32),
+		_ => None,
+	}
+}
+
+
+/// This function writes a character for alphabet file. Characters that cannot be written as themselves,
+/// i.e. whitespaces, control characters, `#`, `@`, `\` and the characters that are not ascii, are written as `\xHH`,
+/// or as `\u{H...}` if they do not fit in a byte, e.g. the target of `AmbiguityPolicy::Map`.
+///
+/// # Arguments
+///
+/// * `ch` - character that will be written.
+///
+fn format_character(ch: char) -> std::string::String {
+	if ch.is_ascii_graphic() && !matches!(ch, '#' | '@' | '\\') {
+		ch.to_string()
+	} else if u8::try_from(ch).is_ok() {
+		format!("\\x{:02X}", ch as u32)
+	} else {
+		format!("\\u{{{:X}}}", ch as u32)
 	}
 }
 
 
-/// This function initalizes alphabet encoding with given filename. 
-/// The function opens file and read line by line in order to extract character and 
-/// its value. The format of the file should be as follows:
-/// 
-/// `char` `i32`
-/// 
-/// For the characters that value is not provided, it is set to -1. 
-/// Also, negative values are not accepted.
-/// 
+/// This function creates alphabet encoding with given filename.
+/// The function opens file and read line by line in order to extract characters and
+/// their values. The format of the file is as follows:
+///
+/// ```text
+/// # comments start with '#' and continue until the end of the line
+/// @fold               # from now on, letters are also defined for their other case
+/// @ambiguity skip     # ambiguity policy: split, skip or map followed by a character
+/// @complement A T     # characters are complement of each other, e.g. for reverse complement
+/// A a 0               # characters followed by a value, all characters are aliases of the value
+/// C 1
+/// \x23 2              # characters can be written in hexadecimal, e.g. '#'
+/// ```
+///
+/// Fields are separated by spaces or tabs and blank lines are ignored. Whitespaces, `#`, `@`, `\` and
+/// characters that are not ascii should be written in hexadecimal, as `\xHH` or `\u{H...}`. The first character
+/// given for a value is used to decode that value back.
+///
+/// A character cannot be defined twice and a value cannot be used in different lines. Also, the values
+/// are checked as in `init_coefficients_map`. Characters of complement pairs should be defined in the file,
+/// and each of them can be given in a single pair. Errors name the line of the file.
+///
 /// # Arguments
-/// 
+///
 /// * `_encoding_file` - filename where encodings are provided.
 /// * `verbose` - this parameter is used determine whether to use logging or not.
-/// 
+///
+#[allow(dead_code)]
+pub fn init_coefficients_file(_encoding_file: &str, verbose: bool) -> Result<Alphabet> {
+
+	init_logging(verbose);
+
+	let path = PathBuf::from(_encoding_file);
+	let mut coefficients: Vec<(char, i32)> = Vec::new();
+	let mut defined: HashSet<char> = HashSet::new();
+	let mut values: HashMap<i32, char> = HashMap::new();
+	let mut fold = false;
+	let mut ambiguity = AmbiguityPolicy::default();
+	let mut complements: Vec<(usize, char, char)> = Vec::new();
+
+	let lines = read_lines(&path).map_err(|source| Error::Io { path: path.clone(), source })?;
+
+	for (index, line) in lines.enumerate() {
+		let ip = line.map_err(|source| Error::Io { path: path.clone(), source })?;
+		let syntax = || Error::Syntax { path: path.clone(), line: index + 1, content: ip.clone() };
+		let invalid = |source: Error| Error::Line { path: path.clone(), line: index + 1, source: Box::new(source) };
+
+		let content = ip.split('#').next().unwrap_or("");
+		let fields: Vec<&str> = content.split_whitespace().collect();
+
+		match fields.as_slice() {
+			[] => continue,
+			["@fold"] => { fold = true; continue; },
+			["@ambiguity", "split"] => { ambiguity = AmbiguityPolicy::Split; continue; },
+			["@ambiguity", "skip"] => { ambiguity = AmbiguityPolicy::Skip; continue; },
+			["@ambiguity", "map", ch] => { ambiguity = AmbiguityPolicy::Map(parse_character(ch).ok_or_else(syntax)?); continue; },
+			["@complement", first, second] => {
+				complements.push((index + 1, parse_character(first).ok_or_else(syntax)?, parse_character(second).ok_or_else(syntax)?));
+				continue;
+			},
+			[directive, ..] if directive.starts_with('@') => return Err(syntax()),
+			[_] => return Err(syntax()),
+			_ => {},
+		}
+
+		let value = fields[fields.len()-1].parse::<i32>().map_err(|_| syntax())?;
+		let mut keys: Vec<char> = Vec::new();
+
+		for field in &fields[..fields.len()-1] {
+			let key = parse_character(field).ok_or_else(syntax)?;
+			keys.push(key);
+			if fold && key.is_ascii_alphabetic() {
+				let other = if key.is_ascii_lowercase() { key.to_ascii_uppercase() } else { key.to_ascii_lowercase() };
+				if !keys.contains(&other) && !fields.contains(&other.to_string().as_str()) {
+					keys.push(other);
+				}
+			}
+		}
+
+		if let Some(&previous) = values.get(&value) {
+			return Err(invalid(Error::DuplicateLabel { value, character: keys[0], previous }));
+		}
+		values.insert(value, keys[0]);
+
+		for key in keys {
+			check_coefficient(key, value).map_err(invalid)?;
+			if !defined.insert(key) {
+				return Err(invalid(Error::DuplicateCharacter { character: key }));
+			}
+			coefficients.push((key, value));
+		}
+	}
+
+	let mut alphabet = from_coefficients(coefficients)?;
+	alphabet.ambiguity = ambiguity;
+
+	if !complements.is_empty() {
+		let mut paired: HashSet<char> = HashSet::new();
+		for &(line, first, second) in &complements {
+			let invalid = |source: Error| Error::Line { path: path.clone(), line, source: Box::new(source) };
+			for ch in [first, second] {
+				if !defined.contains(&ch) {
+					return Err(invalid(Error::InvalidComplement { character: ch }));
+				}
+				if !paired.insert(ch) {
+					return Err(invalid(Error::DuplicateCharacter { character: ch }));
+				}
+			}
+		}
+		let pairs: Vec<(char, char)> = complements.iter().map(|&(_, first, second)| (first, second)).collect();
+		alphabet = alphabet.with_complement(&pairs)?;
+	}
+
+	encoding_summary(&alphabet);
+
+	Ok(alphabet)
+}
+
+
+/// This function writes the alphabet encoding in the format that is read by `init_coefficients_file`.
+/// Each value is written in a single line, starting with the character that is used to decode it back
+/// and followed by its aliases. Hence, alphabet that is read back is the same alphabet.
+///
+/// # Arguments
+///
+/// * `alphabet` - alphabet that will be written.
+///
 #[allow(dead_code)]
-pub fn init_coefficients_file(_encoding_file: &str, verbose: bool) {
+pub fn format_coefficients(alphabet: &Alphabet) -> std::string::String {
 
-	let mut map: HashMap<char, i32> = HashMap::new();
+	let mut aliases: Vec<Vec<char>> = vec![Vec::new(); alphabet.characters.len()];
 
-	if let Ok(lines) = read_lines(_encoding_file) {
-		for line in lines {
-			if let Ok(ip) = line {
-				let splitted: Vec<&str>= ip.split(" ").collect();
-				assert_eq!(splitted.len(), 2);
-				assert_eq!(splitted[0].len(), 1);
-				map.insert(splitted[0].chars().next().expect("string is empty"), splitted[1].parse::<i32>().unwrap());
+	for (ch, value) in alphabet.labels.iter().enumerate() {
+		if *value >= 0 && (ch as u8) as char != alphabet.characters[*value as usize] {
+			aliases[*value as usize].push((ch as u8) as char);
+		}
+	}
+
+	let mut content = std::string::String::from("# rust-lcp alphabet encoding\n");
+
+	match alphabet.ambiguity {
+		AmbiguityPolicy::Split => content.push_str("@ambiguity split\n"),
+		AmbiguityPolicy::Skip => content.push_str("@ambiguity skip\n"),
+		AmbiguityPolicy::Map(ch) => content.push_str(&format!("@ambiguity map {}\n", format_character(ch))),
+	}
+
+	if let Some(complement) = &alphabet.complement {
+		for (ch, other) in complement.iter().enumerate() {
+			if ch < *other as usize {
+				content.push_str(&format!("@complement {} {}\n", format_character((ch as u8) as char), format_character(*other as char)));
 			}
 		}
 	}
 
-	init_coefficients_map(map, verbose);
-}
\ No newline at end of file
+	for (value, ch) in alphabet.characters.iter().enumerate() {
+		if alphabet.label(*ch as u8) != Some(value as i32) {
+			continue;
+		}
+		content.push_str(&format_character(*ch));
+		for alias in &aliases[value] {
+			content.push(' ');
+			content.push_str(&format_character(*alias));
+		}
+		content.push_str(&format!(" {}\n", value));
+	}
+
+	content
+}
+
+
+/// This function writes the alphabet encoding to given filename, see `format_coefficients`.
+///
+/// # Arguments
+///
+/// * `alphabet` - alphabet that will be written.
+/// * `_encoding_file` - filename where encodings will be written.
+///
+#[allow(dead_code)]
+pub fn write_coefficients_file(alphabet: &Alphabet, _encoding_file: &str) -> Result<()> {
+	fs::write(_encoding_file, format_coefficients(alphabet)).map_err(|source| Error::Io { path: PathBuf::from(_encoding_file), source })
+}
@@ -0,0 +1,82 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use crate::statics::{MAX_COMPRESSION_ITERATION_COUNT, SERIALIZATION_VERSION};
+
+
+/// Errors that can be returned by the library instead of terminating the process.
+#[derive(Debug)]
+pub enum Error {
+	/// Label given for a character is negative or does not fit in a single byte.
+	InvalidLabel { character: char, value: i32 },
+	/// Character cannot be represented in a single byte.
+	InvalidCharacter { character: char },
+	/// Character is not part of the alphabet.
+	UnknownSymbol { character: char },
+	/// Alphabet does not contain any character.
+	EmptyAlphabet,
+	/// Character is given more than once.
+	DuplicateCharacter { character: char },
+	/// Value is already used for another character, which is not an alias.
+	DuplicateLabel { value: i32, character: char, previous: char },
+	/// Alphabet file cannot be opened or read.
+	Io { path: PathBuf, source: io::Error },
+	/// Line of the alphabet file does not follow the format.
+	Syntax { path: PathBuf, line: usize, content: String },
+	/// Line of the alphabet file is well formed but its content is invalid.
+	Line { path: PathBuf, line: usize, source: Box<Error> },
+	/// Number of compression iterations is not in the supported range.
+	InvalidIterationCount { count: usize },
+	/// Character given in a complement pair is not part of the alphabet.
+	InvalidComplement { character: char },
+	/// Alphabet does not define complements of the characters.
+	MissingComplement,
+	/// Serialized data is written with a format version that is not supported.
+	UnsupportedVersion { version: u8 },
+	/// Serialized data is not valid.
+	Corrupted { reason: &'static str },
+	/// Sequence cannot be read from the reader.
+	Read { source: io::Error },
+}
+
+
+/// Result type used in the library.
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+impl fmt::Display for Error {
+
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::InvalidLabel { character, value } => write!(f, "invalid value ({}) provided for {:?}, values should be in range [0, 255]", value, character),
+			Error::InvalidCharacter { character } => write!(f, "invalid character {:?}, characters should be in range of a single byte", character),
+			Error::UnknownSymbol { character } => write!(f, "character {:?} is not part of the alphabet", character),
+			Error::EmptyAlphabet => write!(f, "alphabet does not contain any character"),
+			Error::DuplicateCharacter { character } => write!(f, "character {:?} is defined more than once", character),
+			Error::DuplicateLabel { value, character, previous } => write!(f, "value ({}) provided for {:?} is already used for {:?}, aliases should be given in the same line", value, character, previous),
+			Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+			Error::Syntax { path, line, content } => write!(f, "{}:{}: cannot parse {:?}, expected characters followed by a value or a directive", path.display(), line, content),
+			Error::Line { path, line, source } => write!(f, "{}:{}: {}", path.display(), line, source),
+			Error::InvalidIterationCount { count } => write!(f, "invalid compression iteration count ({}), it should be in range [1, {}]", count, MAX_COMPRESSION_ITERATION_COUNT),
+			Error::InvalidComplement { character } => write!(f, "character {:?} of complement pair is not part of the alphabet", character),
+			Error::MissingComplement => write!(f, "alphabet does not define complements of the characters"),
+			Error::UnsupportedVersion { version } => write!(f, "unsupported format version ({}), supported version is {}", version, SERIALIZATION_VERSION),
+			Error::Corrupted { reason } => write!(f, "corrupted data: {}", reason),
+			Error::Read { source } => write!(f, "cannot read the sequence: {}", source),
+		}
+	}
+}
+
+
+impl error::Error for Error {
+
+	fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+		match self {
+			Error::Io { source, .. } => Some(source),
+			Error::Read { source } => Some(source),
+			Error::Line { source, .. } => Some(source.as_ref()),
+			_ => None,
+		}
+	}
+}
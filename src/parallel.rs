@@ -0,0 +1,274 @@
+use crate::encoding::{Alphabet, AmbiguityPolicy};
+use crate::core::Core;
+use crate::{String, Window};
+use std::cmp;
+use std::iter;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+
+/// Job that is run by a worker of the pool.
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+
+/// Pool of worker threads that runs the jobs of `String::from_u8_parallel` and `String::deepen_parallel`.
+/// Workers are started once, when the pool is created, and they are reused by every call, level and
+/// compression iteration until the pool is dropped. Jobs own the data they process, hence the parallel
+/// functions share the input with them through `Arc` or move the cores to them chunk by chunk.
+pub struct ThreadPool {
+	/// Sender of the jobs, it is closed when the pool is dropped so that the workers stop.
+	sender: Option<mpsc::Sender<Job>>,
+	/// Worker threads of the pool.
+	workers: Vec<thread::JoinHandle<()>>,
+}
+
+
+impl ThreadPool {
+
+	/// Constructor of ThreadPool with given number of worker threads.
+	///
+	/// # Arguments
+	///
+	/// * `threads` - number of threads, it is at least 1.
+	///
+	pub fn new(threads: usize) -> Self {
+
+		let (sender, receiver) = mpsc::channel::<Job>();
+		let receiver = Arc::new(Mutex::new(receiver));
+
+		let workers = ( 0..cmp::max(threads, 1) ).map(|_| {
+			let receiver = Arc::clone(&receiver);
+			thread::spawn(move || loop {
+				// lock is released before the job is run, so that the other workers can receive jobs
+				let job = receiver.lock().unwrap().recv();
+				match job {
+					// a panicking job is reported by `run`, the worker is kept for the following jobs
+					Ok(job) => { let _ = panic::catch_unwind(AssertUnwindSafe(job)); },
+					Err(_) => break,
+				}
+			})
+		}).collect();
+
+		ThreadPool { sender: Some(sender), workers }
+	}
+
+	/// This function returns the number of worker threads of the pool.
+	pub fn threads(&self) -> usize {
+		self.workers.len()
+	}
+
+	/// This function runs given tasks on the workers and waits for all of them.
+	///
+	/// # Arguments
+	///
+	/// * `tasks` - tasks that are run, each of them as a single job.
+	///
+	/// Returns the results of the tasks, in the order of the tasks. It panics if a task panics.
+	///
+	pub(crate) fn run<T, F>(&self, tasks: Vec<F>) -> Vec<T>
+	where T: Send + 'static, F: FnOnce() -> T + Send + 'static {
+
+		let count = tasks.len();
+		let (sender, receiver) = mpsc::channel::<(usize, T)>();
+
+		for (index, task) in tasks.into_iter().enumerate() {
+			let sender = sender.clone();
+			let job: Job = Box::new(move || { let _ = sender.send((index, task())); });
+			self.sender.as_ref().expect("thread pool is running").send(job).expect("workers of the thread pool are running");
+		}
+		drop(sender);
+
+		// receiving ends when every job is finished, results of the panicking jobs are missing
+		let mut results: Vec<Option<T>> = iter::repeat_with(|| None).take(count).collect();
+		for (index, result) in receiver {
+			results[index] = Some(result);
+		}
+
+		results.into_iter().map(|result| result.expect("a job of the thread pool panicked")).collect()
+	}
+}
+
+
+impl Drop for ThreadPool {
+	fn drop(&mut self) {
+		// closing the channel stops the workers once they finish their jobs
+		self.sender.take();
+		for worker in self.workers.drain(..) {
+			let _ = worker.join();
+		}
+	}
+}
+
+
+impl String {
+
+	/// Constructor of String with given [u8], whose cores are found by the workers of given pool. The string
+	/// is split into chunks of windows, one for each worker, and each worker processes the windows of a chunk
+	/// while reading the characters beyond its chunk, hence cores that span the chunk edges are found as well.
+	/// Cores are the same as the ones of [`String::from_u8`], in the same order, for any [`AmbiguityPolicy`]
+	/// of the alphabet. The characters are copied once to be shared with the workers.
+	///
+	/// # Arguments
+	///
+	/// * `string` - string given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	/// * `pool` - thread pool whose workers process the chunks.
+	///
+	pub fn from_u8_parallel(string: &[u8], alphabet: &Alphabet, pool: &ThreadPool) -> Self {
+
+		// too small strings are reported as in `from_u8`
+		if string.len() < 3 {
+			return Self::from_u8(string, alphabet);
+		}
+
+		let mut resolution = Self::resolve(string, alphabet);
+		let skip = resolution.ambiguity == AmbiguityPolicy::Skip && resolution.ambiguous_count > 0;
+		let read: Arc<Vec<u8>> = Arc::new(resolution.mapped.take().unwrap_or_else(|| string.to_vec()));
+		let segments: Arc<Vec<(usize, usize)>> = Arc::new(resolution.segments.clone());
+		let alphabet: Arc<Alphabet> = Arc::new(alphabet.clone());
+
+		let tasks: Vec<_> = Self::chunks(string.len(), pool.threads()).into_iter().map(|(first, last)| {
+			let (read, segments, alphabet) = (Arc::clone(&read), Arc::clone(&segments), Arc::clone(&alphabet));
+			move || {
+				let mut cores: Vec<Core> = Vec::new();
+
+				// segments are sorted, so the ones that overlap with the chunk are consecutive
+				let overlapping = segments.partition_point(|&(_, end)| end <= first);
+
+				for &(start, end) in &segments[overlapping..] {
+					if start >= last { break; }
+
+					for index in cmp::max(start, first)..cmp::min(end.saturating_sub(4), last) {
+						match Self::find_window(|position| read[position], end, index, |ch| alphabet.labels[ch as usize], |ch| skip && alphabet.is_ambiguous(ch)) {
+							Window::Empty => {},
+							Window::Core(start, end) => cores.push( Core::from_u8_unchecked(start, &read[start..end], &alphabet) ),
+							Window::Incomplete => break,
+						}
+					}
+				}
+
+				cores
+			}
+		}).collect();
+
+		let cores: Vec<Core> = pool.run(tasks).into_iter().flatten().collect();
+
+		Self::from_resolution(resolution, cores)
+	}
+
+	/// This fuction increases the level multiple times as `deepen_multiple` does, while each level is processed
+	/// by the workers of given pool. Compression is done chunk by chunk, and the left neighbour of each chunk
+	/// is copied before each iteration, so that every core is compressed with the same neighbour as in `dct`.
+	/// New cores are then found chunk by chunk from the compressed cores of the whole level, hence the result,
+	/// including the hierarchy and the dropped cores, is the same as `deepen_multiple`.
+	///
+	/// # Arguments
+	///
+	/// * `level` - number of times that lcp needs to be called.
+	/// * `pool` - thread pool whose workers process the chunks.
+	///
+	pub fn deepen_parallel(&mut self, level: u32, pool: &ThreadPool) {
+		for _ in 0..level {
+
+			// Keep cores before compression if the hierarchy is being kept
+			let previous: Option<Vec<Core>> = self.hierarchy.as_ref().map(|_| self.cores.clone());
+
+			self.dct_parallel(pool);
+
+			let first = 2*self.config.compression_iteration_count()-1;
+			let config = self.config;
+			let chunks = Self::chunks(self.cores.len().saturating_sub(4), pool.threads());
+			// compressed cores are replaced by the new ones, so they are moved to be shared with the workers
+			let compressed: Arc<Vec<Core>> = Arc::new(mem::take(&mut self.cores));
+
+			let tasks: Vec<_> = chunks.into_iter().map(|(start, end)| {
+				let compressed = Arc::clone(&compressed);
+				move || {
+					let mut cores: Vec<Core> = Vec::new();
+					let mut children: Vec<(usize, usize)> = Vec::new();
+					let mut dropped: usize = 0;
+					Self::find_cores(&compressed, cmp::max(start, first)..end, &config, true, &mut cores, &mut children, &mut dropped);
+					(cores, children, dropped)
+				}
+			}).collect();
+
+			let mut cores: Vec<Core> = Vec::new();
+			let mut children: Vec<(usize, usize)> = Vec::new();
+			let mut dropped: usize = 0;
+			for (chunk_cores, chunk_children, chunk_dropped) in pool.run(tasks) {
+				cores.extend(chunk_cores);
+				children.extend(chunk_children);
+				dropped += chunk_dropped;
+			}
+
+			self.complete_level(previous, cores, children, dropped);
+		}
+	}
+
+	/// This function compresses the cores as `dct` does, by the workers of given pool. Cores are moved to the
+	/// workers chunk by chunk, and each iteration is done in place for each chunk, from right to left. The
+	/// first core of a chunk is compressed with a copy of its left neighbour taken before the iteration.
+	///
+	/// # Arguments
+	///
+	/// * `pool` - thread pool whose workers process the chunks.
+	///
+	fn dct_parallel(&mut self, pool: &ThreadPool) {
+
+		if self.cores.len() < 2 { return; }
+
+		let length = self.cores.len();
+		let size = Self::chunks(length, pool.threads())[0].1;
+
+		// chunks are split from the end, so that each core is moved once
+		let mut rest = mem::take(&mut self.cores);
+		let mut chunks: Vec<Vec<Core>> = ( 0..length ).step_by(size).rev().map(|start| rest.split_off(start)).collect();
+		chunks.reverse();
+
+		for iter_index in 0..self.config.compression_iteration_count() {
+
+			// left neighbours of the chunks are copied, as `dct` compresses each core with its neighbour before the iteration
+			let neighbours: Vec<Option<Core>> = iter::once(None).chain(chunks.iter().map(|chunk| chunk.last().cloned())).collect();
+
+			let tasks: Vec<_> = mem::take(&mut chunks).into_iter().zip(neighbours).zip(( 0.. ).step_by(size)).map(|((mut chunk, neighbour), offset)| {
+				move || {
+					for index in ( 1..chunk.len() ).rev() {
+						if offset + index <= iter_index { break; }
+						let (lhs, rhs) = chunk.split_at_mut(index);
+						rhs[0].compress(&lhs[index-1]);
+					}
+					if let Some(neighbour) = neighbour {
+						if offset > iter_index {
+							chunk[0].compress(&neighbour);
+						}
+					}
+					chunk
+				}
+			}).collect();
+
+			chunks = pool.run(tasks);
+		}
+
+		self.cores = Vec::with_capacity(length);
+		for chunk in chunks {
+			self.cores.extend(chunk);
+		}
+	}
+
+	/// This function splits given number of items into consecutive chunks of nearly equal size, one for each thread.
+	/// There is at least one chunk, even if there are no items.
+	///
+	/// # Arguments
+	///
+	/// * `length` - number of items.
+	/// * `threads` - number of threads, it is at least 1.
+	///
+	/// Returns the start and end indexes of the chunks.
+	///
+	fn chunks(length: usize, threads: usize) -> Vec<(usize, usize)> {
+		let size = cmp::max(length.div_ceil(cmp::max(threads, 1)), 1);
+		( 0..cmp::max(length, 1) ).step_by(size).map(|start| (start, cmp::min(start + size, length))).collect()
+	}
+}
@@ -0,0 +1,96 @@
+use crate::statics::SERIALIZATION_VERSION;
+use crate::error::{Error, Result};
+
+
+/// This function appends given value as varint (LEB128), 7 bits per byte starting from the least
+/// significant bits, where the highest bit of each byte tells whether more bytes follow.
+///
+/// # Arguments
+///
+/// * `value` - value that will be written.
+/// * `bytes` - buffer that the value is appended to.
+///
+pub fn write_varint(mut value: u64, bytes: &mut Vec<u8>) {
+	while value >= 0x80 {
+		bytes.push(( value as u8 & 0x7F ) | 0x80);
+		value >>= 7;
+	}
+	bytes.push(value as u8);
+}
+
+
+/// This function reads a varint (LEB128) written by `write_varint`.
+///
+/// # Arguments
+///
+/// * `bytes` - buffer that the value is read from.
+/// * `position` - position of the value in the buffer, it is moved after the value.
+///
+pub fn read_varint(bytes: &[u8], position: &mut usize) -> Result<u64> {
+	let mut value: u64 = 0;
+	let mut shift = 0;
+	loop {
+		let byte = *bytes.get(*position).ok_or(Error::Corrupted { reason: "unexpected end of bytes" })?;
+		*position += 1;
+		if shift > 63 || ( shift == 63 && byte > 1 ) {
+			return Err(Error::Corrupted { reason: "varint overflows 64 bits" });
+		}
+		value |= ( ( byte & 0x7F ) as u64 ) << shift;
+		if byte & 0x80 == 0 {
+			return Ok(value);
+		}
+		shift += 7;
+	}
+}
+
+
+/// This function reads a varint as usize, see `read_varint`.
+///
+/// # Arguments
+///
+/// * `bytes` - buffer that the value is read from.
+/// * `position` - position of the value in the buffer, it is moved after the value.
+///
+pub fn read_usize(bytes: &[u8], position: &mut usize) -> Result<usize> {
+	usize::try_from(read_varint(bytes, position)?).map_err(|_| Error::Corrupted { reason: "value does not fit in usize" })
+}
+
+
+/// This function maps a signed difference to unsigned value (zigzag encoding), so that small
+/// differences in both directions have short varints.
+///
+/// # Arguments
+///
+/// * `value` - signed value.
+///
+pub fn zigzag(value: i64) -> u64 {
+	( ( value << 1 ) ^ ( value >> 63 ) ) as u64
+}
+
+
+/// This function maps a value encoded by `zigzag` back to signed value.
+///
+/// # Arguments
+///
+/// * `value` - unsigned value.
+///
+pub fn unzigzag(value: u64) -> i64 {
+	( value >> 1 ) as i64 ^ -( ( value & 1 ) as i64 )
+}
+
+
+/// This function reads and checks the format version, which is the first byte of the serialized data.
+///
+/// # Arguments
+///
+/// * `bytes` - buffer that the version is read from.
+/// * `position` - position of the version in the buffer, it is moved after the version.
+///
+pub fn read_version(bytes: &[u8], position: &mut usize) -> Result<()> {
+	let version = *bytes.get(*position).ok_or(Error::Corrupted { reason: "unexpected end of bytes" })?;
+	*position += 1;
+	if version != SERIALIZATION_VERSION {
+		return Err(Error::UnsupportedVersion { version });
+	}
+	Ok(())
+}
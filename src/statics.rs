@@ -1,13 +1,22 @@
-// Encoding related static variables
-pub static mut LABELS: [i32; 128] = [-1; 128];
-pub static mut CHARACTERS: [char; 128] = [126 as char; 128];
-pub static mut DICT_BIT_SIZE: usize = 0;
-pub static mut ENCODING_INIT: bool = false;
+use std::sync::Once;
 
 // LCP algorithm related static variables
-pub const COMPRESSION_ITERATION_COUNT: usize = 2;
-pub const CORE_LENGTH: usize = 5; // 3 + 2 (COMPRESSION_ITERATION_COUNT)
+pub const COMPRESSION_ITERATION_COUNT: usize = 2; // default, see Config
+pub const MAX_COMPRESSION_ITERATION_COUNT: usize = 8;
+pub const MAX_SPAN: usize = 10000; // default, see Config
 pub const SIZE_PER_BLOCK: usize = 8;
+pub const INLINE_BLOCK_NUMBER: usize = 8; // blocks stored without heap allocation, 64 bits
+
+// Fingerprint related static variables
+pub const FINGERPRINT_VERSION: u32 = 1; // changes only if the values of Core::fingerprint change
+
+// Serialization related static variables
+pub const SERIALIZATION_VERSION: u8 = 1; // first byte of Core::to_bytes and String::to_bytes
+
+// Alphabet learning related static variables
+pub const LEARNING_SAMPLE_SIZE: usize = 1 << 16;
+pub const LEARNING_PERMUTATION_LIMIT: usize = 5;
+pub const LEARNING_EVALUATION_LIMIT: usize = 256; // each evaluation parses the sample once
 
 // Other
-pub static mut LOG_INIT: bool = false;
\ No newline at end of file
+pub static LOG_INIT: Once = Once::new();
@@ -0,0 +1,472 @@
+use crate::encoding::{Alphabet, AmbiguityPolicy};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::core::{Core, fingerprint_labels};
+use crate::{String, Window};
+use std::collections::VecDeque;
+use std::cmp;
+use std::mem;
+use std::io::{BufRead, ErrorKind};
+
+
+/// Streaming parser that reads a sequence from a reader chunk by chunk and yields its level-1 cores,
+/// without keeping the whole sequence in memory. Cores are the same as the ones of `String::from_u8`
+/// with the whole sequence, in the same order. Only the symbols of the windows that are not processed
+/// yet are kept, which are the last 4 symbols, or the whole run if the sequence ends with a run.
+///
+/// Ambiguous characters are handled by the [`AmbiguityPolicy`] of the alphabet, as the whole sequence would be.
+pub struct CoreReader<'a, R: BufRead> {
+	/// Reader of the sequence.
+	reader: R,
+	/// Alphabet encoding used to label the characters.
+	alphabet: &'a Alphabet,
+	/// Ambiguity policy that is applied.
+	ambiguity: AmbiguityPolicy,
+	/// Symbols of the current segment, starting from the first window that is not processed.
+	buffer: Vec<u8>,
+	/// Position of the first symbol of the buffer within the sequence.
+	offset: usize,
+	/// Number of characters read so far.
+	position: usize,
+	/// Cores that are found but not yielded yet.
+	pending: VecDeque<Core>,
+	/// Number of characters that are not part of the alphabet, read so far.
+	ambiguous_count: usize,
+	/// Whether the reader is exhausted or failed.
+	finished: bool,
+}
+
+
+impl<'a, R: BufRead> CoreReader<'a, R> {
+
+	/// Constructor of CoreReader with given reader.
+	///
+	/// # Arguments
+	///
+	/// * `reader` - reader of the sequence, e.g. `BufReader` of a file.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	///
+	pub fn new(reader: R, alphabet: &'a Alphabet) -> Self {
+		CoreReader {
+			reader,
+			alphabet,
+			ambiguity: String::resolve_policy(alphabet),
+			buffer: Vec::new(),
+			offset: 0,
+			position: 0,
+			pending: VecDeque::new(),
+			ambiguous_count: 0,
+			finished: false,
+		}
+	}
+
+	/// This function returns the number of characters read so far that are not part of the alphabet.
+	pub fn ambiguous_count(&self) -> usize {
+		self.ambiguous_count
+	}
+
+	/// This function finds the cores of the buffered symbols and keeps the ones that are required for the
+	/// following windows. If the segment is complete, every window is processed and the buffer is cleared.
+	///
+	/// # Arguments
+	///
+	/// * `complete` - whether the buffered symbols reach the end of the segment.
+	///
+	fn parse(&mut self, complete: bool) {
+
+		let alphabet = self.alphabet;
+		let skip = self.ambiguity == AmbiguityPolicy::Skip;
+		let mut cores: Vec<Core> = Vec::new();
+
+		let processed = String::parse_symbols(
+			&self.buffer,
+			self.offset,
+			|ch| alphabet.labels[ch as usize],
+			|ch| skip && alphabet.is_ambiguous(ch),
+			|position, substring| Core::from_u8_unchecked(position, substring, alphabet),
+			complete,
+			&mut cores
+		);
+		self.pending.extend(cores);
+
+		if complete {
+			self.offset += self.buffer.len();
+			self.buffer.clear();
+		} else {
+			self.buffer.drain(..processed);
+			self.offset += processed;
+		}
+	}
+
+	/// This function reads the next chunk of the sequence and finds its cores. Segments are completed
+	/// at ambiguous characters if the sequence is split, and at the end of the sequence.
+	fn read_chunk(&mut self) -> Result<()> {
+
+		let chunk = loop {
+			match self.reader.fill_buf() {
+				Ok(chunk) => break chunk.to_vec(),
+				Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+				Err(source) => return Err(Error::Read { source }),
+			}
+		};
+		self.reader.consume(chunk.len());
+
+		if chunk.is_empty() {
+			self.parse(true);
+			self.finished = true;
+			return Ok(());
+		}
+
+		for ch in chunk {
+			self.position += 1;
+			if !self.alphabet.is_ambiguous(ch) {
+				self.buffer.push(ch);
+				continue;
+			}
+			self.ambiguous_count += 1;
+			match self.ambiguity {
+				AmbiguityPolicy::Split => {
+					self.parse(true);
+					self.offset = self.position;
+				},
+				AmbiguityPolicy::Skip => self.buffer.push(ch),
+				AmbiguityPolicy::Map(target) => self.buffer.push(target as u8),
+			}
+		}
+
+		self.parse(false);
+
+		Ok(())
+	}
+}
+
+
+impl<R: BufRead> Iterator for CoreReader<'_, R> {
+	type Item = Result<Core>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(core) = self.pending.pop_front() {
+				return Some(Ok(core));
+			}
+			if self.finished {
+				return None;
+			}
+			if let Err(error) = self.read_chunk() {
+				self.finished = true;
+				return Some(Err(error));
+			}
+		}
+	}
+}
+
+
+/// Lazy parser that scans a sequence and yields the start and end positions and the fingerprint (see
+/// `Core::fingerprint`) of its level-1 cores, without creating them. Cores are the same as the ones of
+/// [`String::from_u8`], in the same order. Windows are processed as the cores are requested and ambiguous
+/// characters (see [`AmbiguityPolicy`]) are handled on the fly, hence neither the cores nor a copy of the
+/// sequence are allocated.
+pub struct CoreScanner<'a> {
+	/// Sequence that is scanned.
+	sequence: &'a [u8],
+	/// Alphabet encoding used to label the characters.
+	alphabet: &'a Alphabet,
+	/// Ambiguity policy that is applied.
+	ambiguity: AmbiguityPolicy,
+	/// Seed of the fingerprints.
+	seed: u64,
+	/// Position of the first symbol of the next window.
+	index: usize,
+	/// End position of the current segment.
+	end: usize,
+}
+
+
+impl<'a> CoreScanner<'a> {
+
+	/// Constructor of CoreScanner with given sequence.
+	///
+	/// # Arguments
+	///
+	/// * `sequence` - sequence given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	/// * `seed` - seed of the fingerprints, see `Core::fingerprint`.
+	///
+	pub fn new(sequence: &'a [u8], alphabet: &'a Alphabet, seed: u64) -> Self {
+		let mut scanner = CoreScanner {
+			sequence,
+			alphabet,
+			ambiguity: String::resolve_policy(alphabet),
+			seed,
+			index: 0,
+			end: 0,
+		};
+		scanner.segment(0);
+		scanner
+	}
+
+	/// This function starts the segment at given position, which ends at the next ambiguous character
+	/// if the sequence is split, and at the end of the sequence otherwise.
+	///
+	/// # Arguments
+	///
+	/// * `start` - start position of the segment.
+	///
+	fn segment(&mut self, start: usize) {
+		self.index = start;
+		self.end = match self.ambiguity {
+			AmbiguityPolicy::Split => self.sequence[start..].iter()
+				.position(|&ch| self.alphabet.is_ambiguous(ch))
+				.map_or(self.sequence.len(), |length| start + length),
+			_ => self.sequence.len(),
+		};
+	}
+
+	/// This function returns the character at given position, after ambiguous characters are mapped.
+	///
+	/// # Arguments
+	///
+	/// * `position` - position of the character within the sequence.
+	///
+	#[inline(always)]
+	fn symbol(&self, position: usize) -> u8 {
+		let ch = self.sequence[position];
+		match self.ambiguity {
+			AmbiguityPolicy::Map(target) if self.alphabet.is_ambiguous(ch) => target as u8,
+			_ => ch,
+		}
+	}
+}
+
+
+impl Iterator for CoreScanner<'_> {
+	type Item = (usize, usize, u64);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let alphabet = self.alphabet;
+		let skip = self.ambiguity == AmbiguityPolicy::Skip;
+		loop {
+			// windows consist of 5 characters, so the segment is over if less are left
+			if self.index + 5 > self.end {
+				if self.end >= self.sequence.len() {
+					return None;
+				}
+				self.segment(self.end + 1);
+				continue;
+			}
+
+			let index = self.index;
+			self.index += 1;
+
+			match String::find_window(|position| self.symbol(position), self.end, index, |ch| alphabet.labels[ch as usize], |ch| skip && alphabet.is_ambiguous(ch)) {
+				Window::Empty => {},
+				Window::Core(start, end) => {
+					let labels = ( start..end ).map(|position| alphabet.labels[self.symbol(position) as usize] as u8);
+					return Some((start, end, fingerprint_labels(labels, end - start, alphabet.dict_bit_size, self.seed)));
+				},
+				Window::Incomplete => self.index = self.end,
+			}
+		}
+	}
+}
+
+
+/// Streaming stage that increases the level of a core stream once, as `String::deepen` does for a whole level.
+/// Cores are compressed as they are pushed, since compression of a core only depends on its left neighbour,
+/// and new cores are found as soon as their windows are complete. Only the cores that can still be part
+/// of a window are kept, which are bounded by the left context of the cores and the length of the runs.
+pub struct Deepener {
+	/// Configuration of the lcp algorithm.
+	config: Config,
+	/// Input of each compression iteration for the previous core, before it is compressed in that iteration.
+	previous: Vec<Option<Core>>,
+	/// Number of cores pushed so far.
+	count: usize,
+	/// Compressed cores that can still be part of a window.
+	buffer: Vec<Core>,
+	/// Index of the first core of the buffer within the level.
+	base: usize,
+	/// Index of the first window that is not processed within the level.
+	window: usize,
+	/// Number of candidate cores dropped as they exceed the maximum span.
+	dropped: usize,
+	/// Index ranges of the children of the found cores, which are not used but kept to reuse its allocation.
+	children: Vec<(usize, usize)>,
+}
+
+
+impl Deepener {
+
+	/// Constructor of Deepener with given configuration.
+	///
+	/// # Arguments
+	///
+	/// * `config` - configuration of the lcp algorithm.
+	///
+	pub fn new(config: Config) -> Self {
+		Deepener {
+			config,
+			previous: vec![None; config.compression_iteration_count()],
+			count: 0,
+			buffer: Vec::new(),
+			base: 0,
+			window: 2 * config.compression_iteration_count() - 1,
+			dropped: 0,
+			children: Vec::new(),
+		}
+	}
+
+	/// This function returns the number of candidate cores dropped so far as they exceed the maximum span.
+	pub fn dropped(&self) -> usize {
+		self.dropped
+	}
+
+	/// This function pushes the next core of the level, and the cores of the next level that are found are pushed into `output`.
+	///
+	/// # Arguments
+	///
+	/// * `core` - next core of the level.
+	/// * `output` - vector that found cores are pushed into.
+	///
+	pub fn push(&mut self, core: Core, output: &mut Vec<Core>) {
+
+		// deterministic coin tossing, each iteration compresses the core with respect to its left neighbour from previous iteration
+		let mut value = core;
+		for (iter_index, previous) in self.previous.iter_mut().enumerate() {
+			let lhs = previous.replace(value.clone());
+			if self.count > iter_index {
+				value.compress(lhs.as_ref().unwrap());
+			}
+		}
+		self.count += 1;
+		self.buffer.push(value);
+
+		self.find(false, output);
+	}
+
+	/// This function completes the level, so the remaining windows are processed and their cores are pushed into `output`.
+	///
+	/// # Arguments
+	///
+	/// * `output` - vector that found cores are pushed into.
+	///
+	pub fn finish(&mut self, output: &mut Vec<Core>) {
+		self.find(true, output);
+		self.buffer.clear();
+	}
+
+	/// This function finds the cores of the buffered windows and drops the cores that are no longer needed.
+	///
+	/// # Arguments
+	///
+	/// * `complete` - whether the level is complete.
+	/// * `output` - vector that found cores are pushed into.
+	///
+	fn find(&mut self, complete: bool, output: &mut Vec<Core>) {
+
+		self.children.clear();
+		let processed = String::find_cores(&self.buffer, self.window - self.base..self.buffer.len().saturating_sub(4), &self.config, complete, output, &mut self.children, &mut self.dropped);
+		self.window = self.base + processed;
+
+		// cores from the left of the next window are used as the left context of the cores
+		let keep = self.window + 1 - self.config.compression_iteration_count();
+		let count = cmp::min(keep.saturating_sub(self.base), self.buffer.len());
+		self.buffer.drain(..count);
+		self.base += count;
+	}
+}
+
+
+/// Streaming pipeline that increases the level of a level-1 core stream, such as `CoreReader`, multiple times.
+/// Each level is deepened by a `Deepener`, hence cores of the target level are yielded in the same order as the
+/// cores of `String::deepen_multiple`, while only the windows in progress are kept in memory.
+pub struct CoreDeepener<I: Iterator<Item = Result<Core>>> {
+	/// Stream of the level-1 cores.
+	cores: I,
+	/// Stage of each level.
+	stages: Vec<Deepener>,
+	/// Cores of the target level that are found but not yielded yet.
+	pending: VecDeque<Core>,
+	/// Whether the stream is exhausted or failed.
+	finished: bool,
+	/// Buffers that cores are passed between the stages with, kept to reuse their allocations.
+	buffers: (Vec<Core>, Vec<Core>),
+}
+
+
+impl<I: Iterator<Item = Result<Core>>> CoreDeepener<I> {
+
+	/// Constructor of CoreDeepener with given core stream.
+	///
+	/// # Arguments
+	///
+	/// * `cores` - stream of the cores to be deepened.
+	/// * `config` - configuration of the lcp algorithm.
+	/// * `level` - number of times the level is increased.
+	///
+	pub fn new(cores: I, config: Config, level: u32) -> Self {
+		CoreDeepener {
+			cores,
+			stages: ( 0..level ).map(|_| Deepener::new(config)).collect(),
+			pending: VecDeque::new(),
+			finished: false,
+			buffers: (Vec::new(), Vec::new()),
+		}
+	}
+
+	/// This function returns the number of candidate cores dropped so far at each level, see `String::dropped`.
+	pub fn dropped(&self) -> Vec<usize> {
+		self.stages.iter().map(|stage| stage.dropped()).collect()
+	}
+
+	/// This function passes given core through the stages and keeps the cores of the target level.
+	/// If there is no core, the stream is exhausted, and the stages are completed one after another.
+	///
+	/// # Arguments
+	///
+	/// * `core` - next core of the stream, None if the stream is exhausted.
+	///
+	fn pass(&mut self, core: Option<Core>) {
+		let complete = core.is_none();
+		let (mut input, mut output) = mem::take(&mut self.buffers);
+		input.extend(core);
+		for stage in &mut self.stages {
+			for core in input.drain(..) {
+				stage.push(core, &mut output);
+			}
+			if complete {
+				stage.finish(&mut output);
+			}
+			mem::swap(&mut input, &mut output);
+		}
+		self.pending.extend(input.drain(..));
+		self.buffers = (input, output);
+	}
+}
+
+
+impl<I: Iterator<Item = Result<Core>>> Iterator for CoreDeepener<I> {
+	type Item = Result<Core>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(core) = self.pending.pop_front() {
+				return Some(Ok(core));
+			}
+			if self.finished {
+				return None;
+			}
+			match self.cores.next() {
+				Some(Ok(core)) => self.pass(Some(core)),
+				Some(Err(error)) => {
+					self.finished = true;
+					return Some(Err(error));
+				},
+				None => {
+					self.pass(None);
+					self.finished = true;
+				},
+			}
+		}
+	}
+}
@@ -1,15 +1,24 @@
 pub mod statics;
+pub mod error;
 pub mod encoding;
 pub mod core;
-use crate::statics::COMPRESSION_ITERATION_COUNT;
-use crate::statics::LABELS;
-use crate::statics::ENCODING_INIT;
-use crate::encoding::init_coefficients_default;
+pub mod config;
+pub mod serialize;
+pub mod stream;
+pub mod parallel;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::serialize::{write_varint, read_varint, read_usize, read_version, zigzag, unzigzag};
+use crate::statics::{SERIALIZATION_VERSION, MAX_COMPRESSION_ITERATION_COUNT};
+use crate::encoding::{Alphabet, AmbiguityPolicy, TextAlphabet};
 use crate::core::Core;
 use std::cmp;
-use log::error;
+use std::fmt;
+use std::ops::Range;
+use log::{error, warn};
 
 
+#[derive(Debug)]
 pub struct String {
 	/// Level of LCP algorithm being called. This parameter
 	/// is autamatically updated as deepen() func called.
@@ -19,7 +28,74 @@ pub struct String {
 	/// order in terms of start indexes. I have used VecDeque as I
 	/// needed to insert to tail and remove from head efficiently while 
 	/// increasing the level.
-	pub cores: Vec<Core>
+	pub cores: Vec<Core>,
+	/// Policy that was applied to the characters that are not part of the alphabet.
+	/// If the alphabet asks for mapping to a character that is not in the alphabet,
+	/// `Split` is reported as it is the one being applied.
+	pub ambiguity: AmbiguityPolicy,
+	/// Number of characters in the original string that are not part of the alphabet.
+	pub ambiguous_count: usize,
+	/// Segments (start and end positions) of the original string that were parsed.
+	/// Unless the sequence is split at ambiguous characters, there is a single segment
+	/// that covers whole string.
+	pub segments: Vec<(usize, usize)>,
+	/// Byte offset of each character and of the end of the original string. It is only available
+	/// for strings processed over Unicode characters, where positions of the cores are character offsets.
+	pub byte_offsets: Option<Vec<usize>>,
+	/// Cores of the previous levels and the child cores of each core. It is only available
+	/// if the hierarchy is kept (see `keep_hierarchy`), as it requires to store every level.
+	pub hierarchy: Option<Hierarchy>,
+	/// Configuration of the lcp algorithm used while increasing the level.
+	pub config: Config,
+	/// Number of candidate cores dropped as they exceed the maximum span (see `Config::with_max_span`),
+	/// for each increased level. First value is the number of cores dropped while moving to the second level.
+	pub dropped: Vec<usize>,
+	/// Orientation of each core, only available for the cores of strand-canonical strings (see `from_u8_canonical`).
+	pub strands: Option<Vec<Strand>>,
+}
+
+
+/// Orientation of a strand-canonical core, i.e. whether its label is read from the given sequence
+/// or from its reverse complement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strand {
+	Forward,
+	Reverse,
+}
+
+
+/// Parse hierarchy of a String, which is retained while increasing the level.
+#[derive(Debug, Clone, Default)]
+pub struct Hierarchy {
+	/// Level of the String when the hierarchy is started to be kept, which is the first retained level.
+	pub base: u32,
+	/// Cores of the retained levels before the current level, starting from `base`. Cores are stored
+	/// before compression, as they were at their level.
+	pub levels: Vec<Vec<Core>>,
+	/// Index range (start and end) of the child cores, within the level below, that each core was built from.
+	/// It is stored for each level after `base`, including the current level.
+	pub children: Vec<Vec<(usize, usize)>>,
+}
+
+
+/// Ambiguity policy resolved for a given string, with the sequence to be parsed.
+pub(crate) struct Resolution {
+	pub(crate) ambiguity: AmbiguityPolicy,
+	pub(crate) ambiguous_count: usize,
+	pub(crate) segments: Vec<(usize, usize)>,
+	pub(crate) mapped: Option<Vec<u8>>,
+}
+
+
+/// Result of lcp algorithm for a single window (see `find_window`).
+pub(crate) enum Window {
+	/// Window does not have a core.
+	Empty,
+	/// Window has a core, spanning given start and end positions.
+	Core(usize, usize),
+	/// Window starts a run that reaches the end of the symbols. The run may continue beyond the end, so neither
+	/// this window nor the following ones can have a core until the following symbols are known, if there are any.
+	Incomplete,
 }
 
 
@@ -27,160 +103,554 @@ impl String {
 
 	/// Constructor of String with given str. This String does not stores the actual character array
 	/// but the cores and level that defınes the lcp.
+	/// Characters are labelled and ambiguous ones are resolved as in [`String::parse`].
+	/// The string is processed byte by byte, and positions are byte offsets. Use `from_text` to process
+	/// the string over Unicode characters.
 	/// 
 	/// # Arguments
 	/// 
 	/// * `str` - string that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
 	///
-	pub fn new(string: &str) -> Self {
-		// str can be converted to &[u8] with as_bytes() func.
-		// hence, there is no need to duplicate code.
-		// return Self::from_u8(&string.as_bytes());
-		unsafe {
-			// make sure that encodings are initialized
-			if !ENCODING_INIT {
-				init_coefficients_default(false);
-			}
+	pub fn new(string: &str, alphabet: &Alphabet) -> Self {
+		Self::parse(string, alphabet)
+	}
 
-			// if minimum lenght is 3 as 0-level cores have length of 3 as there is no compression yet.
-			if string.len() < 3 { 
-				error!("Given string ({}) is too small!", string); 
-				return String {
-					level: 1,
-					cores: Vec::new()
-				};
-			}
+	/// Constructor of String with given [u8]. This String does not stores the actual character array
+	/// but the cores and level that defines the lcp.
+	/// Characters are labelled and ambiguous ones are resolved as in [`String::parse`].
+	/// 
+	/// # Arguments
+	/// 
+	/// * `string` - string given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	///
+	pub fn from_u8(string: &[u8], alphabet: &Alphabet) -> Self {
+		Self::parse(string, alphabet)
+	}
 
-			let mut index2: usize;
-			let end = string.len();
-			let read = string.as_bytes();
-			let mut cores: Vec<Core> = Vec::new();
-			
-			// index here should be taken as index + 1 in read as window does not starts with core but with left neighour
-			// window consists of 5 characters and the core that is being processed is middle 3 character.
-			for (index, window) in read.windows(5).enumerate() {
-				
-				if window[1] == window[2] { continue; }
+	/// Constructor of String with any byte sequence, such as str, [u8] or `Vec<u8>`. Both `new` and `from_u8`
+	/// are processed by this function, hence they give the same String for the same bytes.
+	/// Ambiguous characters are handled by the [`AmbiguityPolicy`] of the alphabet.
+	/// 
+	/// # Arguments
+	/// 
+	/// * `string` - byte sequence that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	///
+	pub fn parse<S: AsRef<[u8]>>(string: S, alphabet: &Alphabet) -> Self {
 
-				// if there are same characters in subsequenct order such as xyyz, xyyyz, .... where x!=y and y!=z
-				if window[2] == window[3] { 
-					index2 = index + 3;
-					
-					let mut prev: u8 = window[3];
-					for ch in &read[index2..] {
-						if prev != *ch  { break; }
-						
-						prev = *ch;
-						index2 += 1;
-					}
+		let string = string.as_ref();
+		
+		// if minimum lenght is 3 as 0-level cores have length of 3 as there is no compression yet.
+		if string.len() < 3 { 
+			error!("Given string ({}) is too small!", std::string::String::from_utf8_lossy(string)); 
+			return Self::empty(string.len(), alphabet.ambiguity);
+		}
 
-					if index2 == end { break; }
+		let resolution = Self::resolve(string, alphabet);
+		let cores = Self::parse_resolved(string, alphabet, &resolution);
 
-					index2 += 1;
-					
-					cores.push( Core::from_str(index + 1, std::str::from_utf8(&read[index+1..index2]).unwrap() ) );
-					continue;
-				}
+		Self::from_resolution(resolution, cores)
+	}
 
-				// if there is no subsequent characters such as xyz where z!=y and y!=z
+	/// This function finds the level-1 cores of given [u8] after its ambiguity policy is resolved, so that
+	/// the constructors that need the resolution as well do not resolve the same string again.
+	///
+	/// # Arguments
+	///
+	/// * `string` - string given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	/// * `resolution` - ambiguity policy resolved for the string, see `resolve`.
+	///
+	pub(crate) fn parse_resolved(string: &[u8], alphabet: &Alphabet, resolution: &Resolution) -> Vec<Core> {
+
+		let read = resolution.mapped.as_deref().unwrap_or(string);
+		let skip = resolution.ambiguity == AmbiguityPolicy::Skip && resolution.ambiguous_count > 0;
+		let mut cores: Vec<Core> = Vec::new();
+
+		for &(start, end) in &resolution.segments {
+			Self::parse_symbols(
+				&read[start..end],
+				start,
+				|ch| alphabet.labels[ch as usize],
+				|ch| skip && alphabet.is_ambiguous(ch),
+				|position, substring| Core::from_u8_unchecked(position, substring, alphabet),
+				true,
+				&mut cores
+			);
+		}
+
+		cores
+	}
+
+	/// Constructor of level-1 String with the cores found after given ambiguity resolution.
+	///
+	/// # Arguments
+	///
+	/// * `resolution` - ambiguity policy resolved for the parsed string.
+	/// * `cores` - level-1 cores of the string.
+	///
+	pub(crate) fn from_resolution(resolution: Resolution, cores: Vec<Core>) -> Self {
+		String {
+			level: 1,
+			cores,
+			ambiguity: resolution.ambiguity,
+			ambiguous_count: resolution.ambiguous_count,
+			segments: resolution.segments,
+			byte_offsets: None,
+			hierarchy: None,
+			config: Config::default(),
+			dropped: Vec::new(),
+			strands: None,
+		}
+	}
 
-				// if window[0] == window[1] || window[3] == window[4] { continue; } // should we add this?
+	/// Constructor of String with given text, processed over Unicode characters instead of bytes.
+	/// Characters are labeled with given text alphabet. Text is split into segments at the characters
+	/// that are not part of the alphabet. Positions of the cores are character offsets, and the
+	/// corresponding byte offsets are available with `byte_range`.
+	///
+	/// # Arguments
+	///
+	/// * `text` - text that will be processed with lcp algorithm.
+	/// * `alphabet` - text alphabet encoding used to label the characters, see `TextAlphabet::from_text`.
+	///
+	pub fn from_text(text: &str, alphabet: &TextAlphabet) -> Self {
 
-				if LABELS[window[2] as usize] < LABELS[window[1] as usize] && LABELS[window[2] as usize] < LABELS[window[3] as usize] ||	// local minima
-					(
-						LABELS[window[2] as usize] > LABELS[window[1] as usize] && LABELS[window[2] as usize] > LABELS[window[3] as usize] &&       // local maxima without immediate local minima neighbours
-						!(LABELS[window[1] as usize] < LABELS[window[2] as usize] && LABELS[window[1] as usize] < LABELS[window[0] as usize]) &&
-						!(LABELS[window[3] as usize] < LABELS[window[4] as usize] && LABELS[window[3] as usize] < LABELS[window[2] as usize]) 
-					) 
-				{
-					cores.push( Core::from_str(index + 1, std::str::from_utf8(&window[1..4]).unwrap() ) ); 
+		let mut byte_offsets: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+		let labels: Vec<Option<u32>> = text.chars().map(|ch| alphabet.label(ch)).collect();
+		byte_offsets.push(text.len());
+
+		// if minimum lenght is 3 as 0-level cores have length of 3 as there is no compression yet.
+		if labels.len() < 3 {
+			error!("Given string ({}) is too small!", text);
+			let mut string = Self::empty(labels.len(), AmbiguityPolicy::Split);
+			string.byte_offsets = Some(byte_offsets);
+			return string;
+		}
+
+		let mut segments: Vec<(usize, usize)> = Vec::new();
+		let mut cores: Vec<Core> = Vec::new();
+		let mut start = 0;
+
+		for index in 0..=labels.len() {
+			if index == labels.len() || labels[index].is_none() {
+				if start < index {
+					let segment: Vec<u32> = labels[start..index].iter().flatten().copied().collect();
+					Self::parse_symbols(
+						&segment,
+						start,
+						|label| label,
+						|_| false,
+						|position, labels| Core::from_labels(position, labels, alphabet.dict_bit_size),
+						true,
+						&mut cores
+					);
+					segments.push((start, index));
 				}
-			}
-			
-			String {
-				level: 1,
-				cores: cores
+				start = index + 1;
 			}
 		}
+
+		String {
+			level: 1,
+			cores,
+			ambiguity: AmbiguityPolicy::Split,
+			ambiguous_count: labels.iter().filter(|label| label.is_none()).count(),
+			segments,
+			byte_offsets: Some(byte_offsets),
+			hierarchy: None,
+			config: Config::default(),
+			dropped: Vec::new(),
+			strands: None,
+		}
 	}
 
-	/// Constructor of String with given [u8]. This String does not stores the actual character array
-	/// but the cores and level that defines the lcp.
-	/// 
+	/// This function returns the start and end byte offsets of given core in the original string.
+	/// For strings that are processed byte by byte, they are the positions of the core.
+	///
 	/// # Arguments
-	/// 
+	///
+	/// * `core` - core of this String.
+	///
+	pub fn byte_range(&self, core: &Core) -> (usize, usize) {
+		match &self.byte_offsets {
+			Some(byte_offsets) => (byte_offsets[core.start], byte_offsets[core.end]),
+			None => (core.start, core.end),
+		}
+	}
+
+	/// This function returns start, end and decoded substring of each core. Cores can only be decoded
+	/// at the first level, None is returned if the string is deepened or processed over Unicode characters.
+	///
+	/// # Arguments
+	///
+	/// * `alphabet` - alphabet encoding used while processing the string.
+	///
+	pub fn get_sequences(&self, alphabet: &Alphabet) -> Option<Vec<(usize, usize, Vec<u8>)>> {
+
+		if self.level != 1 || self.byte_offsets.is_some() {
+			return None;
+		}
+
+		self.cores.iter().map(|core| core.decode(alphabet).map(|sequence| (core.start, core.end, sequence))).collect()
+	}
+
+	/// This function serializes the level and the cores of the String to compact bytes, which can be read back
+	/// with `from_bytes`. Other information, such as the ambiguity report, hierarchy and configuration, is not stored.
+	/// The format is: format version (`SERIALIZATION_VERSION`), level and number of cores as varints, followed by
+	/// the cores. Each core is written as the difference of its start to the start of the previous core (zigzag
+	/// varint), its length (varint) and its label (see `Core::to_bytes`).
+	///
+	/// # Arguments
+	///
+	/// * `self` - this function needs to access level and cores.
+	///
+	pub fn to_bytes(&self) -> Vec<u8> {
+
+		let mut bytes: Vec<u8> = vec![SERIALIZATION_VERSION];
+		let mut previous: usize = 0;
+
+		write_varint(self.level as u64, &mut bytes);
+		write_varint(self.cores.len() as u64, &mut bytes);
+
+		for core in &self.cores {
+			write_varint(zigzag(core.start as i64 - previous as i64), &mut bytes);
+			write_varint(( core.end - core.start ) as u64, &mut bytes);
+			core.write_label(&mut bytes);
+			previous = core.start;
+		}
+
+		bytes
+	}
+
+	/// Constructor of String from bytes written by `to_bytes`. As only the level and the cores are stored,
+	/// other fields are as if the string was parsed as a single segment without any ambiguous character.
+	///
+	/// # Arguments
+	///
+	/// * `bytes` - serialized String, without any trailing bytes.
+	///
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+
+		let mut position: usize = 0;
+		read_version(bytes, &mut position)?;
+
+		let level = u32::try_from(read_varint(bytes, &mut position)?).map_err(|_| Error::Corrupted { reason: "level does not fit in u32" })?;
+		let count = read_usize(bytes, &mut position)?;
+		// each core needs at least 4 bytes, so the capacity is not taken from corrupted data blindly
+		let mut cores: Vec<Core> = Vec::with_capacity(cmp::min(count, bytes.len() / 4));
+		let mut previous: usize = 0;
+
+		for _ in 0..count {
+			let start = ( previous as i64 ).checked_add(unzigzag(read_varint(bytes, &mut position)?))
+				.and_then(|start| usize::try_from(start).ok())
+				.ok_or(Error::Corrupted { reason: "position is out of range" })?;
+			let end = start.checked_add(read_usize(bytes, &mut position)?).ok_or(Error::Corrupted { reason: "position overflows" })?;
+			cores.push(Core::read_label(bytes, &mut position, start, end)?);
+			previous = start;
+		}
+
+		if position != bytes.len() {
+			return Err(Error::Corrupted { reason: "trailing bytes" });
+		}
+
+		let mut string = Self::empty(cores.iter().map(|core| core.end).max().unwrap_or(0), AmbiguityPolicy::default());
+		string.level = level;
+		string.cores = cores;
+
+		Ok(string)
+	}
+
+	/// This function processes given [u8] on both strands, so that the cores do not depend on the strand the
+	/// sequence is read from. Both the sequence and its reverse complement (using the complements of the alphabet)
+	/// are processed, and the cores of the reverse complement are mapped back to the positions of the sequence.
+	/// Each core covers the same substring on both strands, and its label is the smaller one of the labels of
+	/// the substring and of its reverse complement, the orientation of the chosen one is given in `strands`.
+	/// Hence, a sequence and its reverse complement have the same cores at mirrored positions, with opposite
+	/// orientations (unless the substring is its own reverse complement).
+	/// Ambiguous characters are their own complement, and the ambiguity policy is applied to each strand
+	/// after the reverse complement is taken, e.g. an ambiguous character is mapped to the same character on both strands.
+	///
+	/// # Arguments
+	///
 	/// * `string` - string given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding that defines complements of the characters.
 	///
-	pub fn from_u8(string: &[u8]) -> Self {
-		
-		unsafe {
-			// make sure that encodings are initialized
-			if !ENCODING_INIT {
-				init_coefficients_default(false);
-			}
+	pub fn from_u8_canonical(string: &[u8], alphabet: &Alphabet) -> Result<Self> {
 
-			// if minimum lenght is 3 as 0-level cores have length of 3 as there is no compression yet.
-			if string.len() < 3 { 
-				error!("Given string ({}) is too small!", std::str::from_utf8(string).unwrap()); 
-				return String {
-					level: 1,
-					cores: Vec::new()
-				};
+		let complement = alphabet.complement.as_ref().ok_or(Error::MissingComplement)?;
+
+		// too small strings are reported as in `from_u8`
+		if string.len() < 3 {
+			let mut forward = Self::from_u8(string, alphabet);
+			forward.strands = Some(Vec::new());
+			return Ok(forward);
+		}
+
+		// reverse strand is built from the given sequence, not the mapped one, so that mapping is the same on both strands.
+		// each strand is resolved once, and the resolution is used both to find the cores and to label them
+		let resolution = Self::resolve(string, alphabet);
+		let reverse: Vec<u8> = string.iter().rev().map(|ch| complement[*ch as usize]).collect();
+		let reverse_resolution = Self::resolve(&reverse, alphabet);
+
+		// positions of the cores on both strands, with respect to the given sequence
+		let mut positions: Vec<(usize, usize)> = Self::parse_resolved(string, alphabet, &resolution).iter().map(|core| (core.start, core.end)).collect();
+		positions.extend(Self::parse_resolved(&reverse, alphabet, &reverse_resolution).iter().map(|core| (string.len() - core.end, string.len() - core.start)));
+		positions.sort();
+		positions.dedup();
+
+		let read = resolution.mapped.as_deref().unwrap_or(string);
+		let reverse = reverse_resolution.mapped.as_deref().unwrap_or(&reverse);
+		let mut cores: Vec<Core> = Vec::with_capacity(positions.len());
+		let mut strands: Vec<Strand> = Vec::with_capacity(positions.len());
+
+		for (start, end) in positions {
+			let forward_core = Core::from_u8_unchecked(start, &read[start..end], alphabet);
+			let reverse_core = Core::from_u8_unchecked(start, &reverse[string.len() - end..string.len() - start], alphabet);
+			if reverse_core < forward_core {
+				cores.push(reverse_core);
+				strands.push(Strand::Reverse);
+			} else {
+				cores.push(forward_core);
+				strands.push(Strand::Forward);
 			}
+		}
 
-			let mut index2: usize;
-			let end = string.len();
-			let mut cores: Vec<Core> = Vec::new();
-			
-			// index here should be taken as index + 1 in read as window does not starts with core but with left neighour
-			// window consists of 5 characters and the core that is being processed is middle 3 character.
-			for (index, window) in string.windows(5).enumerate() {
+		let mut forward = Self::from_resolution(resolution, cores);
+		forward.strands = Some(strands);
 
-				if window[1] == window[2] { continue; }
-				
-				// if there are same characters in subsequenct order such as xyyz, xyyyz, .... where x!=y and y!=z
+		Ok(forward)
+	}
 
-				if window[2] == window[3] {
+	/// This function processes given [u8] once for each seed, with the alphabet whose labels are permuted
+	/// with that seed (see `Alphabet::permute`). Since the cores depend on the labels, each String is an
+	/// independent parse of the same string.
+	///
+	/// # Arguments
+	///
+	/// * `string` - string given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding whose labels are permuted.
+	/// * `seeds` - seeds of the permutations, one String is returned for each of them.
+	///
+	pub fn from_u8_ensemble(string: &[u8], alphabet: &Alphabet, seeds: &[u64]) -> Vec<Self> {
+		seeds.iter().map(|seed| Self::from_u8(string, &alphabet.permute(*seed))).collect()
+	}
 
-					index2 = index + 3;
+	/// This function merges the cores of given Strings of the same string into a single String.
+	/// Cores are ordered by their positions and if more than one String has a core at the same
+	/// position, the one that is given first is kept. As the labels of the cores may come from
+	/// different alphabets, merged String is meant to be used for the positions of the cores.
+	///
+	/// # Arguments
+	///
+	/// * `strings` - Strings of the same string at the same level, e.g. from `from_u8_ensemble`.
+	///
+	pub fn merge(strings: Vec<Self>) -> Self {
 
-					let mut prev: u8 = window[3];
-					for ch in &string[index2..] {
-						if prev != *ch  { break; }
-						
-						prev = *ch;
-						index2 += 1;
-					}
+		let mut strings = strings.into_iter();
+		let mut merged = match strings.next() {
+			Some(string) => string,
+			None => return Self::empty(0, AmbiguityPolicy::default()),
+		};
 
-					if index2 == end { break; }
+		for string in strings {
+			merged.cores.extend(string.cores);
+		}
 
-					index2 += 1;
-					cores.push( Core::from_u8(index + 1, &string[index+1..index2]) );
-					continue;
-				}
+		// sort is stable, hence the core of the first String comes first at the same position
+		merged.cores.sort_by_key(|core| (core.start, core.end));
+		merged.cores.dedup_by_key(|core| (core.start, core.end));
+		merged.hierarchy = None;
+		merged.strands = None;
 
-				// if there is no subsequent characters such as xyz where z!=y and y!=z
+		merged
+	}
 
-				// if window[0] == window[1] || window[3] == window[4] { continue; } // should we add this?
+	/// Constructor of String without any cores. It is used when given string is too small.
+	///
+	/// # Arguments
+	///
+	/// * `len` - length of the given string.
+	/// * `ambiguity` - ambiguity policy that is reported.
+	///
+	fn empty(len: usize, ambiguity: AmbiguityPolicy) -> Self {
+		String {
+			level: 1,
+			cores: Vec::new(),
+			ambiguity,
+			ambiguous_count: 0,
+			segments: vec![(0, len)],
+			byte_offsets: None,
+			hierarchy: None,
+			config: Config::default(),
+			dropped: Vec::new(),
+			strands: None,
+		}
+	}
+
+	/// This function returns the ambiguity policy that is applied with given alphabet. If the alphabet asks for
+	/// mapping to a character that is not in the alphabet, `Split` is applied instead.
+	///
+	/// # Arguments
+	///
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	///
+	pub(crate) fn resolve_policy(alphabet: &Alphabet) -> AmbiguityPolicy {
+		if let AmbiguityPolicy::Map(ch) = alphabet.ambiguity {
+			if u8::try_from(ch).map_or(true, |ch| alphabet.is_ambiguous(ch)) {
+				error!("Ambiguous characters cannot be mapped to {}, as it is not part of the alphabet. Sequence is split instead.", ch);
+				return AmbiguityPolicy::Split;
+			}
+		}
+		alphabet.ambiguity
+	}
 
-				if LABELS[window[2] as usize] < LABELS[window[1] as usize] && LABELS[window[2] as usize] < LABELS[window[3] as usize] ||	// local minima
-					(
-						LABELS[window[2] as usize] > LABELS[window[1] as usize] && LABELS[window[2] as usize] > LABELS[window[3] as usize] &&       // local maxima without immediate local minima neighbours
-						!(LABELS[window[1] as usize] < LABELS[window[2] as usize] && LABELS[window[1] as usize] < LABELS[window[0] as usize]) &&
-						!(LABELS[window[3] as usize] < LABELS[window[4] as usize] && LABELS[window[3] as usize] < LABELS[window[2] as usize]) 
-					) 
-				{
-					cores.push( Core::from_u8(index + 1, &window[1..4]) ); 
+	/// This function applies ambiguity policy of the alphabet to the given string. It counts the ambiguous
+	/// characters and finds the segments to be parsed. If ambiguous characters are mapped, mapped copy of the
+	/// string is created only when there is at least one ambiguous character.
+	///
+	/// # Arguments
+	///
+	/// * `string` - string given in [u8] format that will be processed with lcp algorithm.
+	/// * `alphabet` - alphabet encoding used to label the characters.
+	///
+	pub(crate) fn resolve(string: &[u8], alphabet: &Alphabet) -> Resolution {
+
+		let ambiguous_count = string.iter().filter(|&&ch| alphabet.is_ambiguous(ch)).count();
+
+		let ambiguity = Self::resolve_policy(alphabet);
+
+		let mut resolution = Resolution {
+			ambiguity,
+			ambiguous_count,
+			segments: vec![(0, string.len())],
+			mapped: None,
+		};
+
+		if ambiguous_count == 0 {
+			return resolution;
+		}
+
+		match ambiguity {
+			AmbiguityPolicy::Split => {
+				resolution.segments.clear();
+				let mut start = 0;
+				for (index, ch) in string.iter().enumerate() {
+					if alphabet.is_ambiguous(*ch) {
+						if start < index {
+							resolution.segments.push((start, index));
+						}
+						start = index + 1;
+					}
+				}
+				if start < string.len() {
+					resolution.segments.push((start, string.len()));
 				}
+			},
+			AmbiguityPolicy::Skip => {},
+			AmbiguityPolicy::Map(target) => {
+				resolution.mapped = Some(string.iter().map(|&ch| if alphabet.is_ambiguous(ch) { target as u8 } else { ch }).collect());
+			},
+		}
+
+		resolution
+	}
+
+	/// This function finds the cores of given symbols with lcp algorithm. Symbols are compared for equality
+	/// to find runs, and their labels are compared to find local minima and maxima. It is used by every
+	/// constructor, whatever the symbols are, e.g. bytes labeled by `Alphabet` or labels of `TextAlphabet`.
+	///
+	/// # Arguments
+	///
+	/// * `symbols` - symbols of the substring, that are not skipped unless `skipped` says so.
+	/// * `offset` - start position of the substring within original string.
+	/// * `label` - returns the label of a symbol.
+	/// * `skipped` - returns whether a symbol is ambiguous, windows and cores that contain it are skipped.
+	/// * `build` - creates a Core from its start position and symbols.
+	/// * `complete` - whether the symbols reach the end of the substring, otherwise processing stops at the first window that needs the following symbols.
+	/// * `cores` - vector that found cores are pushed into.
+	///
+	/// Returns the index of the first window that is not processed, so that it can be continued when more symbols are given.
+	///
+	pub(crate) fn parse_symbols<T, L, F, S, B>(symbols: &[T], offset: usize, label: F, skipped: S, build: B, complete: bool, cores: &mut Vec<Core>) -> usize
+	where T: Copy + PartialEq, L: PartialOrd, F: Fn(T) -> L, S: Fn(T) -> bool, B: Fn(usize, &[T]) -> Core {
+
+		// index here should be taken as index + 1 in read as window does not starts with core but with left neighour
+		// window consists of 5 characters and the core that is being processed is middle 3 character.
+		for index in 0..symbols.len().saturating_sub(4) {
+			match Self::find_window(|position| symbols[position], symbols.len(), index, &label, &skipped) {
+				Window::Empty => {},
+				Window::Core(start, end) => cores.push( build(offset + start, &symbols[start..end]) ),
+				Window::Incomplete => {
+					if complete { break; }
+					return index;
+				},
 			}
+		}
+
+		symbols.len().saturating_sub(4)
+	}
+
+	/// This function applies lcp algorithm to a single window of 5 symbols, starting at given index.
+	/// Symbols are accessed by their positions, so that they can be read without being copied,
+	/// e.g. while ambiguous characters are mapped on the fly.
+	///
+	/// # Arguments
+	///
+	/// * `symbol` - returns the symbol at a position, which is less than `end`.
+	/// * `end` - end position of the symbols, runs cannot extend beyond it.
+	/// * `index` - position of the first symbol of the window, which is the left neighbour of the core.
+	/// * `label` - returns the label of a symbol.
+	/// * `skipped` - returns whether a symbol is ambiguous, windows and cores that contain it are skipped.
+	///
+	pub(crate) fn find_window<T, L, P, F, S>(symbol: P, end: usize, index: usize, label: F, skipped: S) -> Window
+	where T: Copy + PartialEq, L: PartialOrd, P: Fn(usize) -> T, F: Fn(T) -> L, S: Fn(T) -> bool {
+
+		let window = [symbol(index), symbol(index+1), symbol(index+2), symbol(index+3), symbol(index+4)];
+
+		if window.iter().any(|&ch| skipped(ch)) { return Window::Empty; }
+
+		if window[1] == window[2] { return Window::Empty; }
+
+		// if there are same characters in subsequenct order such as xyyz, xyyyz, .... where x!=y and y!=z
 
-			String {
-				level: 1,
-				cores: cores
+		if window[2] == window[3] {
+
+			let mut index2 = index + 4;
+			while index2 < end && symbol(index2) == window[3] {
+				index2 += 1;
 			}
+
+			if index2 == end { return Window::Incomplete; }
+
+			index2 += 1;
+
+			if skipped(symbol(index2-1)) { return Window::Empty; }
+
+			return Window::Core(index + 1, index2);
+		}
+
+		// if there is no subsequent characters such as xyz where z!=y and y!=z
+
+		// if window[0] == window[1] || window[3] == window[4] { continue; } // should we add this?
+
+		let labels = [label(window[0]), label(window[1]), label(window[2]), label(window[3]), label(window[4])];
+
+		if (labels[2] < labels[1] && labels[2] < labels[3]) ||	// local minima
+			(
+				labels[2] > labels[1] && labels[2] > labels[3] &&       // local maxima without immediate local minima neighbours
+				labels[1] >= labels[0] &&
+				labels[3] >= labels[4]
+			) 
+		{
+			return Window::Core(index + 1, index + 4);
 		}
+
+		Window::Empty
 	}
-	
+
 
 	/// This fuction calls lcp algorithm to increase level multiple time. 
 	/// Instead of calling lcp multiple times as if the number is greater than 2,
@@ -213,7 +683,11 @@ impl String {
 	/// to compose new core. The number of cores from left hand side to be taken will be determined from the number of iteration
 	/// of compression is done.
 	/// 
-	/// (3 + COMPRESSION_ITERATION_COUNT == NEW CORE LENGTH at new level)
+	/// (3 + compression iteration count == NEW CORE LENGTH at new level, see `Config`)
+	/// 
+	/// Candidate cores whose window spans the maximum span of the configuration or more characters are dropped,
+	/// and the number of them is recorded in `dropped`. Cores are not strand-canonical after increasing the level,
+	/// hence `strands` is cleared.
 	/// 
 	/// # Arguments
 	/// 
@@ -221,17 +695,76 @@ impl String {
 	///
 	pub fn deepen(&mut self) {
 
+		// Keep cores before compression if the hierarchy is being kept
+		let previous: Option<Vec<Core>> = self.hierarchy.as_ref().map(|_| self.cores.clone());
+
 		// Compress cores
 		self.dct();
 				
 		// Find cores from compressed cores.
-		let end = self.cores.len();
-		let mut index2: usize;
 		let mut cores: Vec<Core> = Vec::new();
+		let mut children: Vec<(usize, usize)> = Vec::new();
+		let mut dropped: usize = 0;
+
+		let windows = 2*self.config.compression_iteration_count()-1..self.cores.len().saturating_sub(4);
+		Self::find_cores(&self.cores, windows, &self.config, true, &mut cores, &mut children, &mut dropped);
+
+		//self.cores.drain(0..end);
+		self.complete_level(previous, cores, children, dropped);
+	}
+
+	/// This function replaces the cores with the cores of the next level, and records the hierarchy and the
+	/// number of dropped cores of the level.
+	///
+	/// # Arguments
+	///
+	/// * `previous` - cores of the current level before compression, if the hierarchy is being kept.
+	/// * `cores` - cores of the next level.
+	/// * `children` - index ranges of the cores that the cores of the next level are built from.
+	/// * `dropped` - number of candidate cores dropped as they exceed the maximum span.
+	///
+	pub(crate) fn complete_level(&mut self, previous: Option<Vec<Core>>, cores: Vec<Core>, children: Vec<(usize, usize)>, dropped: usize) {
 
-		for (index, window) in self.cores.windows(5).enumerate().skip(2*COMPRESSION_ITERATION_COUNT-1) {
+		if let (Some(hierarchy), Some(previous)) = (self.hierarchy.as_mut(), previous) {
+			hierarchy.levels.push(previous);
+			hierarchy.children.push(children);
+		}
+
+		if dropped > 0 {
+			warn!("{} candidate cores spanning at least {} characters are dropped at level {}.", dropped, self.config.max_span().unwrap_or_default(), self.level + 1);
+		}
+
+		self.cores = cores;
+		self.strands = None;
+		self.dropped.push(dropped);
+		self.level += 1;
+	}
+
+	/// This function finds the cores of the next level from the compressed cores of the current level, by
+	/// concatinating the cores that meet the rules of lcp algorithm (see `deepen`).
+	/// # Arguments
+	/// 
+	/// * `compressed` - compressed cores of the current level.
+	/// * `windows` - indexes of the windows to be processed, the windows before `2 * compression iteration count - 1` do not have enough left context.
+	/// * `config` - configuration of the lcp algorithm.
+	/// * `complete` - whether the cores reach the end of the level, otherwise processing stops at the first window that needs the following cores.
+	/// * `cores` - vector that found cores are pushed into.
+	/// * `children` - vector that index ranges of the cores that found cores are built from are pushed into.
+	/// * `dropped` - number of candidate cores dropped as they exceed the maximum span.
+	///
+	/// Returns the index of the first window that is not processed, so that it can be continued when more cores are given.
+	///
+	pub(crate) fn find_cores(compressed: &[Core], windows: Range<usize>, config: &Config, complete: bool, cores: &mut Vec<Core>, children: &mut Vec<(usize, usize)>, dropped: &mut usize) -> usize {
+
+		let end = compressed.len();
+		let core_length = config.core_length();
+		let max_span = config.max_span();
+		let mut index2: usize;
+
+		for (index, window) in compressed.windows(5).enumerate().take(windows.end).skip(windows.start) {
 			
-			if window[4].end - window[0].start >= 10000 { continue; }
+			// candidates whose window spans at least max_span characters are dropped
+			let exceeds = max_span.is_some_and(|max_span| window[4].end - window[0].start >= max_span);
 
 			if window[1] == window[2] { continue; }
 				
@@ -240,18 +773,24 @@ impl String {
 				index2 = index + 3;
 				
 				let mut prev: &Core = &window[3];
-				for ch in &self.cores[index2..] {
+				for ch in &compressed[index2..] {
 					if prev != ch  { break; }
 					
 					prev = ch;
 					index2 += 1;
 				}
 
-				if index2 == end { break; }
+				if index2 == end {
+					if complete { break; }
+					return index;
+				}
 
 				index2 += 1;
+
+				if exceeds { *dropped += 1; continue; }
 				
-				cores.push( Core::from_cores(&self.cores[index+1..index2]) );
+				cores.push( Core::from_cores(&compressed[index+1..index2]) );
+				children.push( (index+1, index2) );
 				continue;
 			}
 
@@ -262,21 +801,105 @@ impl String {
 			if window[2] < window[1] && window[2] < window[3] ||           // local minima
 				(
 					window[2] > window[1] && window[2] > window[3] &&       // local maxima without immediate local minima neighbours
-					!(window[1] < window[2] && window[1] < window[0]) &&
-					!(window[3] < window[4] && window[3] < window[2]) 
+					window[1] >= window[0] &&
+					window[3] >= window[4]
 				)
 			{
-				cores.push( Core::from_cores(&self.cores[index+1-COMPRESSION_ITERATION_COUNT..index+4]) ); 
+				if exceeds { *dropped += 1; continue; }
+
+				cores.push( Core::from_cores(&compressed[index+4-core_length..index+4]) ); 
+				children.push( (index+4-core_length, index+4) );
 			}
 		}
 
-		//self.cores.drain(0..end);
-		self.cores = cores;
-		self.level += 1;
+		cmp::max(windows.start, windows.end)
+	}
+
+	/// This function starts to keep the parse hierarchy, so that the cores of the current and following levels
+	/// are retained while increasing the level, together with the child cores of each core.
+	/// It should be called before `deepen` to retain the first level.
+	/// # Arguments
+	/// 
+	/// * `self` - this function needs to access level.
+	///
+	pub fn keep_hierarchy(&mut self) {
+		if self.hierarchy.is_none() {
+			self.hierarchy = Some(Hierarchy { base: self.level, ..Hierarchy::default() });
+		}
+	}
+
+	/// This function returns the cores of given level, if it is the current level or it is retained in the hierarchy.
+	/// # Arguments
+	/// 
+	/// * `level` - level of the cores.
+	///
+	pub fn get_level(&self, level: u32) -> Option<&[Core]> {
+		if level == self.level {
+			return Some(&self.cores);
+		}
+		let hierarchy = self.hierarchy.as_ref()?;
+		hierarchy.levels.get(level.checked_sub(hierarchy.base)? as usize).map(|cores| cores.as_slice())
+	}
+
+	/// This function returns the index range of the child cores, within `level - 1`, of the core at given index.
+	/// Child cores include the left neighbours that are used in compression, hence neighbouring cores share children.
+	/// # Arguments
+	/// 
+	/// * `level` - level of the core, which should be above the first retained level.
+	/// * `index` - index of the core within its level.
+	///
+	pub fn children(&self, level: u32, index: usize) -> Option<(usize, usize)> {
+		let hierarchy = self.hierarchy.as_ref()?;
+		let children = hierarchy.children.get(level.checked_sub(hierarchy.base + 1)? as usize)?;
+		children.get(index).copied()
+	}
+
+	/// This function returns the indices of the cores at `level + 1` that are built from the core at given index.
+	/// As neighbouring cores share children, a core can have more than one parent, or none if it was not used.
+	/// # Arguments
+	/// 
+	/// * `level` - level of the core.
+	/// * `index` - index of the core within its level.
+	///
+	pub fn parents(&self, level: u32, index: usize) -> Vec<usize> {
+		let children = match self.hierarchy.as_ref().and_then(|hierarchy| level.checked_sub(hierarchy.base).and_then(|offset| hierarchy.children.get(offset as usize))) {
+			Some(children) => children,
+			None => return Vec::new(),
+		};
+
+		// ends of the child ranges do not decrease, as each window ends a core after the run of the previous window.
+		// Starts may decrease after a run, but only by the compression iteration count, so ranges starting farther
+		// than `MAX_COMPRESSION_ITERATION_COUNT` after the index are followed by ranges that do not contain it.
+		let first = children.partition_point(|&(_, end)| end <= index);
+		children[first..].iter()
+			.take_while(|(start, _)| *start <= index + MAX_COMPRESSION_ITERATION_COUNT)
+			.enumerate()
+			.filter(|(_, (start, end))| *start <= index && index < *end)
+			.map(|(offset, _)| first + offset)
+			.collect()
+	}
+
+	/// This function returns the indices of the cores at `ancestor_level` that are built, directly or not, from
+	/// the core at given index.
+	/// # Arguments
+	/// 
+	/// * `level` - level of the core.
+	/// * `index` - index of the core within its level.
+	/// * `ancestor_level` - level of the ancestors, which should be above `level`.
+	///
+	pub fn ancestors(&self, level: u32, index: usize, ancestor_level: u32) -> Vec<usize> {
+		let mut indices: Vec<usize> = vec![index];
+		for current in level..ancestor_level {
+			let mut parents: Vec<usize> = indices.iter().flat_map(|index| self.parents(current, *index)).collect();
+			parents.sort();
+			parents.dedup();
+			indices = parents;
+		}
+		if ancestor_level > level { indices } else { Vec::new() }
 	}
 
 	/// This fuction compresses each core with respect to its left neigbor. The total number of dct
-	/// should be defined as static variable.
+	/// is defined by the configuration of the String.
 	/// # Arguments
 	/// 
 	/// * `self` - this function needs to access core.
@@ -285,7 +908,7 @@ impl String {
 
 		// deterministic coin tossing
 
-		for iter_index in 0..COMPRESSION_ITERATION_COUNT {
+		for iter_index in 0..self.config.compression_iteration_count() {
 
 			if self.cores.len() < 2 { return; }
 
@@ -295,7 +918,7 @@ impl String {
 			let mut rhs = mut_iter.next().unwrap();
 			let mut index = 1;
 
-			while index < end - iter_index {
+			while index + iter_index < end {
 				let lhs = mut_iter.next().unwrap();
 				index += 1;
 
@@ -309,12 +932,12 @@ impl String {
 			// println!("Finding new cores.");
 		}
 
-		// be aware that the first N elements where N=COMPRESSION_ITERATION_COUNT is not removed from the Vec.
+		// be aware that the first N elements where N=compression iteration count is not removed from the Vec.
 		// this is because drain/remove function compies all items in the right to left which makes the operation O(n).
 	}
 
-	/// This fuction returns labels of cores as u64 type.
-	/// For some of the cores, u64 might not be enough to represent core,
+	/// This fuction returns labels of the cores at the current level as u64 type, see `get_small_cores_of_level`
+	/// for the other levels. For some of the cores, u64 might not be enough to represent core,
 	/// as the cores that has repetıtıve character can have great length.
 	/// In that case, only 64 bits from right hand side will be used.
 	/// # Arguments
@@ -328,6 +951,95 @@ impl String {
 		}
 		cores
 	}
+
+	/// This function returns the labels of the cores at the current level as u128, see `get_large_cores_of_level`
+	/// for the other levels. Labels with more than 128 bits are truncated to the 128 bits from right.
+	/// # Arguments
+	/// 
+	/// * `self` - this function needs to access core.
+	///
+	pub fn get_large_cores(&self) -> Vec<u128> {
+		self.cores.iter().map(|core| core.encode_u128()).collect()
+	}
+
+	/// This function returns the hashes of the labels of the cores at the current level, see `get_hashed_cores_of_level`
+	/// for the other levels. Unlike `get_small_cores` and `get_large_cores`, whole label is used regardless of its length.
+	/// # Arguments
+	/// 
+	/// * `self` - this function needs to access core.
+	///
+	pub fn get_hashed_cores(&self) -> Vec<u64> {
+		self.cores.iter().map(|core| core.encode_hash()).collect()
+	}
+
+	/// This function returns the stable fingerprints of the cores at the current level, see `get_fingerprints_of_level`
+	/// for the other levels. Fingerprints can be persisted and compared across machines and releases (see `Core::fingerprint`).
+	/// # Arguments
+	/// 
+	/// * `self` - this function needs to access core.
+	/// * `seed` - seed of the fingerprints.
+	///
+	pub fn get_fingerprints(&self, seed: u64) -> Vec<u64> {
+		self.cores.iter().map(|core| core.fingerprint(seed)).collect()
+	}
+
+	/// This function returns the labels of the cores at given level as u64, as `get_small_cores` does for the
+	/// current level. Previous levels are only available if the hierarchy is kept (see `get_level`).
+	/// # Arguments
+	/// 
+	/// * `level` - level of the cores.
+	///
+	pub fn get_small_cores_of_level(&self, level: u32) -> Option<Vec<u64>> {
+		self.get_level(level).map(|cores| cores.iter().map(|core| core.encode()).collect())
+	}
+
+	/// This function returns the labels of the cores at given level as u128, as `get_large_cores` does for the
+	/// current level. Previous levels are only available if the hierarchy is kept (see `get_level`).
+	/// # Arguments
+	/// 
+	/// * `level` - level of the cores.
+	///
+	pub fn get_large_cores_of_level(&self, level: u32) -> Option<Vec<u128>> {
+		self.get_level(level).map(|cores| cores.iter().map(|core| core.encode_u128()).collect())
+	}
+
+	/// This function returns the hashes of the labels of the cores at given level, as `get_hashed_cores` does for
+	/// the current level. Previous levels are only available if the hierarchy is kept (see `get_level`).
+	/// # Arguments
+	/// 
+	/// * `level` - level of the cores.
+	///
+	pub fn get_hashed_cores_of_level(&self, level: u32) -> Option<Vec<u64>> {
+		self.get_level(level).map(|cores| cores.iter().map(|core| core.encode_hash()).collect())
+	}
+
+	/// This function returns the stable fingerprints of the cores at given level, as `get_fingerprints` does for
+	/// the current level. Previous levels are only available if the hierarchy is kept (see `get_level`).
+	/// # Arguments
+	/// 
+	/// * `level` - level of the cores.
+	/// * `seed` - seed of the fingerprints.
+	///
+	pub fn get_fingerprints_of_level(&self, level: u32, seed: u64) -> Option<Vec<u64>> {
+		self.get_level(level).map(|cores| cores.iter().map(|core| core.fingerprint(seed)).collect())
+	}
+}
+
+
+impl fmt::Display for String {
+	/// String is displayed with its level and number of cores, followed by the cores one per line.
+	/// 
+	/// # Arguments
+	/// * `self` - String that will be displayed.
+	/// * `f` - Formatter that is used.
+	///
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "level {}: {} cores", self.level, self.cores.len())?;
+		for core in &self.cores {
+			write!(f, "\n{}", core)?;
+		}
+		Ok(())
+	}
 }
 
 
@@ -1,17 +1,54 @@
 use crate::encoding::init_coefficients_default;
+use crate::encoding::init_coefficients_protein;
+use crate::encoding::init_coefficients_bytes;
 use crate::encoding::init_coefficients_map;
 use crate::encoding::init_coefficients_file;
-use crate::statics::LABELS;
-use crate::statics::CHARACTERS;
-use crate::statics::DICT_BIT_SIZE;
-use std::collections::HashMap;
+use crate::encoding::write_coefficients_file;
+use crate::encoding::format_coefficients;
+use crate::encoding::init_coefficients_frequency;
+use crate::encoding::core_balance;
+use crate::encoding::AmbiguityPolicy;
+use crate::encoding::TextAlphabet;
+use std::collections::{HashMap, HashSet, BTreeSet};
+use std::cmp::Ordering;
+use std::fs;
+use std::env;
+use std::thread;
 use crate::core::Core;
+use crate::statics::{FINGERPRINT_VERSION, MAX_COMPRESSION_ITERATION_COUNT, LEARNING_EVALUATION_LIMIT};
+use crate::config::Config;
+use crate::error::Error;
 use crate::String;
-
-
-//use std::sync::Mutex;
-
-//static mtx: Mutex<i32>= Mutex::new(0);
+use crate::serialize::{write_varint, zigzag};
+use crate::stream::{CoreReader, CoreDeepener, CoreScanner};
+use crate::parallel::ThreadPool;
+use std::io::{BufReader, Read};
+use crate::Strand;
+
+
+/// This function generates a pseudo-random DNA sequence with xorshift. Sequence contains runs of up to 300
+/// characters and occasional ambiguous characters (N), so that every rule of lcp algorithm is exercised.
+fn random_sequence(length: usize, seed: u64) -> Vec<u8> {
+    let mut state: u64 = 88172645463325252 ^ seed;
+    if state == 0 { state = 1; }
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut sequence: Vec<u8> = Vec::with_capacity(length);
+    while sequence.len() < length {
+        let ch = b"ACGTN"[(next() % 5) as usize];
+        let run = if next() % 50 == 0 { 1 + next() % 300 } else { 1 };
+        if ch != b'N' || next() % 10 == 0 {
+            sequence.extend(std::iter::repeat_n(ch, run as usize));
+        }
+    }
+    sequence.truncate(length);
+    sequence
+}
 
 
 //------------------------------------------------------------------------------------
@@ -21,81 +58,359 @@ use crate::String;
 //------------------------------------------------------------------------------------
 #[test]
 fn test_encoding_default() {
-    //let guard = mtx.lock().unwrap();
-    unsafe {
-        let verbose = true;
-        init_coefficients_default(verbose);
-        
-        assert_eq!(LABELS['A' as usize], 0); 
-        assert_eq!(LABELS['a' as usize], 0);
-        assert_eq!(LABELS['T' as usize], 3); 
-        assert_eq!(LABELS['t' as usize], 3);
-        assert_eq!(LABELS['G' as usize], 2); 
-        assert_eq!(LABELS['g' as usize], 2);
-        assert_eq!(LABELS['C' as usize], 1); 
-        assert_eq!(LABELS['c' as usize], 1);
-        
-        assert_eq!(CHARACTERS[0], 'A');
-        assert_eq!(CHARACTERS[1], 'C');
-        assert_eq!(CHARACTERS[2], 'G');
-        assert_eq!(CHARACTERS[3], 'T');
+    let verbose = true;
+    let alphabet = init_coefficients_default(verbose).unwrap();
+    
+    assert_eq!(alphabet.labels['A' as usize], 0); 
+    assert_eq!(alphabet.labels['a' as usize], 0);
+    assert_eq!(alphabet.labels['T' as usize], 3); 
+    assert_eq!(alphabet.labels['t' as usize], 3);
+    assert_eq!(alphabet.labels['G' as usize], 2); 
+    assert_eq!(alphabet.labels['g' as usize], 2);
+    assert_eq!(alphabet.labels['C' as usize], 1); 
+    assert_eq!(alphabet.labels['c' as usize], 1);
+    
+    assert_eq!(alphabet.characters[0], 'A');
+    assert_eq!(alphabet.characters[1], 'C');
+    assert_eq!(alphabet.characters[2], 'G');
+    assert_eq!(alphabet.characters[3], 'T');
 
-        assert_eq!(DICT_BIT_SIZE, 2);
-    }
-    //drop(guard);
+    assert_eq!(alphabet.dict_bit_size, 2);
+}
+
+
+#[test]
+fn test_encoding_protein() {
+    let verbose = true;
+    let alphabet = init_coefficients_protein(verbose).unwrap();
+
+    assert_eq!(alphabet.labels['A' as usize], 0);
+    assert_eq!(alphabet.labels['a' as usize], 0);
+    assert_eq!(alphabet.labels['M' as usize], 10);
+    assert_eq!(alphabet.labels['m' as usize], 10);
+    assert_eq!(alphabet.labels['Y' as usize], 19);
+    assert_eq!(alphabet.labels['X' as usize], 20);
+    assert_eq!(alphabet.labels['*' as usize], 21);
+    assert_eq!(alphabet.labels['B' as usize], -1);
+    assert_eq!(alphabet.labels['U' as usize], -1);
+
+    assert_eq!(alphabet.characters[0], 'A');
+    assert_eq!(alphabet.characters[10], 'M');
+    assert_eq!(alphabet.characters[21], '*');
+
+    assert_eq!(alphabet.dict_bit_size, 5);
+}
+
+
+#[test]
+fn test_encoding_bytes() {
+    let verbose = true;
+    let alphabet = init_coefficients_bytes(verbose).unwrap();
+
+    assert_eq!(alphabet.labels[0x00], 0);
+    assert_eq!(alphabet.labels['A' as usize], 65);
+    assert_eq!(alphabet.labels[0x80], 128);
+    assert_eq!(alphabet.labels[0xff], 255);
+    assert!((0..=u8::MAX).all(|ch| !alphabet.is_ambiguous(ch)));
+
+    assert_eq!(alphabet.characters[65], 'A');
+    assert_eq!(alphabet.characters[0xe9], 'é');
+
+    assert_eq!(alphabet.dict_bit_size, 8);
+}
+
+
+#[test]
+fn test_encoding_text() {
+    let alphabet = TextAlphabet::from_text("çöğüşı, café çay");
+
+    // labels are in the order of code points
+    assert_eq!(alphabet.characters, [' ', ',', 'a', 'c', 'f', 'y', 'ç', 'é', 'ö', 'ü', 'ğ', 'ı', 'ş']);
+    assert_eq!(alphabet.label(' '), Some(0));
+    assert_eq!(alphabet.label('ş'), Some(12));
+    assert_eq!(alphabet.label('x'), None);
+    assert_eq!(alphabet.dict_bit_size, 4);
+
+    let alphabet = TextAlphabet::from_text("aaaa");
+    assert_eq!(alphabet.dict_bit_size, 1);
 }
 
 
 #[test]
 fn test_encoding_map() {
-    //let guard = mtx.lock().unwrap();
-    unsafe {
-        let verbose = true;
-        let map = HashMap::from([
-            ('a', 3),
-            ('c', 0),
-            ('t', 1),
-            ('g', 2)
-            ]);
-        init_coefficients_map(map, verbose);
-        
-        assert_eq!(LABELS['a' as usize], 3);
-        assert_eq!(LABELS['t' as usize], 1);
-        assert_eq!(LABELS['g' as usize], 2);
-        assert_eq!(LABELS['c' as usize], 0);
-        
-        assert_eq!(CHARACTERS[3], 'a');
-        assert_eq!(CHARACTERS[0], 'c');
-        assert_eq!(CHARACTERS[2], 'g');
-        assert_eq!(CHARACTERS[1], 't');
-
-        assert_eq!(DICT_BIT_SIZE, 2);
-    }
-    //drop(guard);
+    let verbose = true;
+    let map = HashMap::from([
+        ('a', 3),
+        ('c', 0),
+        ('t', 1),
+        ('g', 2)
+        ]);
+    let alphabet = init_coefficients_map(map, verbose).unwrap();
+    
+    assert_eq!(alphabet.labels['a' as usize], 3);
+    assert_eq!(alphabet.labels['t' as usize], 1);
+    assert_eq!(alphabet.labels['g' as usize], 2);
+    assert_eq!(alphabet.labels['c' as usize], 0);
+    
+    assert_eq!(alphabet.characters[3], 'a');
+    assert_eq!(alphabet.characters[0], 'c');
+    assert_eq!(alphabet.characters[2], 'g');
+    assert_eq!(alphabet.characters[1], 't');
+
+    assert_eq!(alphabet.dict_bit_size, 2);
 }
 
 
 #[test]
 fn test_encoding_file() {
-    //let guard = mtx.lock().unwrap();
-    unsafe {
-        let verbose = true;
-        let path = "src/encodings.txt";
-        init_coefficients_file(path, verbose);
-        
-        assert_eq!(LABELS['a' as usize], 3);
-        assert_eq!(LABELS['t' as usize], 1);
-        assert_eq!(LABELS['g' as usize], 2);
-        assert_eq!(LABELS['c' as usize], 0);
-        
-        assert_eq!(CHARACTERS[3], 'a');
-        assert_eq!(CHARACTERS[0], 'c');
-        assert_eq!(CHARACTERS[2], 'g');
-        assert_eq!(CHARACTERS[1], 't');
+    let verbose = true;
+    let path = "src/encodings.txt";
+    let alphabet = init_coefficients_file(path, verbose).unwrap();
+    
+    assert_eq!(alphabet.labels['a' as usize], 3);
+    assert_eq!(alphabet.labels['t' as usize], 1);
+    assert_eq!(alphabet.labels['g' as usize], 2);
+    assert_eq!(alphabet.labels['c' as usize], 0);
+    
+    assert_eq!(alphabet.characters[3], 'a');
+    assert_eq!(alphabet.characters[0], 'c');
+    assert_eq!(alphabet.characters[2], 'g');
+    assert_eq!(alphabet.characters[1], 't');
+
+    assert_eq!(alphabet.dict_bit_size, 2);
+}
 
-        assert_eq!(DICT_BIT_SIZE, 2);
+
+#[test]
+fn test_encoding_errors() {
+    match init_coefficients_map(HashMap::from([('a', 0), ('c', -1)]), false) {
+        Err(Error::InvalidLabel { character, value }) => assert_eq!((character, value), ('c', -1)),
+        other => panic!("unexpected result: {:?}", other),
     }
-    //drop(guard);
+    match init_coefficients_map(HashMap::from([('a', 0), ('c', 256)]), false) {
+        Err(error) => assert!(error.to_string().contains("256") && error.to_string().contains("'c'")),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    match init_coefficients_map(HashMap::from([('a', 0), ('ğ', 1)]), false) {
+        Err(Error::InvalidCharacter { character }) => assert_eq!(character, 'ğ'),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(matches!(init_coefficients_map(HashMap::new(), false), Err(Error::EmptyAlphabet)));
+
+    // single label is stored with 1 bit
+    assert_eq!(init_coefficients_map(HashMap::from([('a', 0)]), false).unwrap().dict_bit_size, 1);
+}
+
+
+#[test]
+fn test_encoding_file_errors() {
+    let path = env::temp_dir().join(format!("rust-lcp-missing-{}.txt", std::process::id()));
+    match init_coefficients_file(path.to_str().unwrap(), false) {
+        Err(Error::Io { path: error_path, .. }) => assert_eq!(error_path, path),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let path = env::temp_dir().join(format!("rust-lcp-syntax-{}.txt", std::process::id()));
+    fs::write(&path, "a 3\nt 1\ng two\nc 0").unwrap();
+    let result = init_coefficients_file(path.to_str().unwrap(), false);
+    fs::remove_file(&path).unwrap();
+    match result {
+        Err(Error::Syntax { line, content, .. }) => assert_eq!((line, content.as_str()), (3, "g two")),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let path = env::temp_dir().join(format!("rust-lcp-value-{}.txt", std::process::id()));
+    fs::write(&path, "a 3\nt -4\ng 2\nc 0").unwrap();
+    let result = init_coefficients_file(path.to_str().unwrap(), false);
+    fs::remove_file(&path).unwrap();
+    match result {
+        Err(error) => {
+            assert!(error.to_string().ends_with(":2: invalid value (-4) provided for 't', values should be in range [0, 255]"));
+            assert!(matches!(error, Error::Line { line: 2, source, .. } if matches!(*source, Error::InvalidLabel { character: 't', value: -4 })));
+        },
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+
+#[test]
+fn test_encoding_file_format() {
+    let path = env::temp_dir().join(format!("rust-lcp-format-{}.txt", std::process::id()));
+    fs::write(&path, "# DNA alphabet with gaps\n\n@ambiguity map A\nA\ta 0   # adenine\n  C c\t1\n\n@fold\nG 2\nt 3\n\\x2D 4\n").unwrap();
+    let alphabet = init_coefficients_file(path.to_str().unwrap(), false);
+    fs::remove_file(&path).unwrap();
+    let alphabet = alphabet.unwrap();
+
+    assert_eq!(alphabet.labels['A' as usize], 0);
+    assert_eq!(alphabet.labels['a' as usize], 0);
+    assert_eq!(alphabet.labels['c' as usize], 1);
+    assert_eq!(alphabet.labels['g' as usize], 2);
+    assert_eq!(alphabet.labels['T' as usize], 3);
+    assert_eq!(alphabet.labels['-' as usize], 4);
+    assert_eq!(alphabet.labels['#' as usize], -1);
+
+    assert_eq!(alphabet.characters[..5], ['A', 'C', 'G', 't', '-']);
+    assert_eq!(alphabet.dict_bit_size, 3);
+    assert_eq!(alphabet.ambiguity, AmbiguityPolicy::Map('A'));
+
+    // same value in different lines
+    let path = env::temp_dir().join(format!("rust-lcp-duplicate-label-{}.txt", std::process::id()));
+    fs::write(&path, "A a 0\nC 1\nc 1\n").unwrap();
+    let result = init_coefficients_file(path.to_str().unwrap(), false);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(Error::Line { line: 3, source, .. }) if matches!(*source, Error::DuplicateLabel { value: 1, character: 'c', previous: 'C' })));
+
+    // character that is defined twice by case folding
+    let path = env::temp_dir().join(format!("rust-lcp-duplicate-character-{}.txt", std::process::id()));
+    fs::write(&path, "@fold\nA 0\nC 1\na 2\n").unwrap();
+    let result = init_coefficients_file(path.to_str().unwrap(), false);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(Error::Line { line: 4, source, .. }) if matches!(*source, Error::DuplicateCharacter { character: 'a' })));
+}
+
+
+#[test]
+fn test_encoding_file_round_trip() {
+    let mut custom = init_coefficients_map(HashMap::from([('#', 0), (' ', 1), ('\\', 2), ('é', 3), ('z', 4)]), false).unwrap();
+    custom.ambiguity = AmbiguityPolicy::Map('#');
+    let mut protein = init_coefficients_protein(false).unwrap();
+    protein.ambiguity = AmbiguityPolicy::Skip;
+
+    // target that does not fit in a byte is written as a Unicode escape
+    let mut unicode = init_coefficients_default(false).unwrap();
+    unicode.ambiguity = AmbiguityPolicy::Map('ğ');
+    assert!(format_coefficients(&unicode).contains("@ambiguity map \\u{11F}\n"));
+
+    let alphabets = [init_coefficients_default(false).unwrap(), protein, init_coefficients_bytes(false).unwrap(), custom, unicode];
+
+    for (index, alphabet) in alphabets.iter().enumerate() {
+        let path = env::temp_dir().join(format!("rust-lcp-round-trip-{}-{}.txt", std::process::id(), index));
+        write_coefficients_file(alphabet, path.to_str().unwrap()).unwrap();
+        let result = init_coefficients_file(path.to_str().unwrap(), false);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&result.unwrap(), alphabet);
+    }
+
+    assert_eq!(format_coefficients(&alphabets[0]), "# rust-lcp alphabet encoding\n@ambiguity split\n@complement A T\n@complement C G\n@complement a t\n@complement c g\nA a 0\nC c 1\nG g 2\nT t 3\n");
+}
+
+
+#[test]
+fn test_encoding_frequency() {
+    let sample = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG".repeat(4);
+
+    let (alphabet, report) = init_coefficients_frequency(sample.as_bytes(), false).unwrap();
+
+    // all assignments of 4 characters are evaluated
+    assert_eq!(report.evaluated, 24);
+    assert_eq!(report.frequencies.iter().map(|(_, count)| count).sum::<usize>(), sample.len());
+    assert_eq!(alphabet.dict_bit_size, 2);
+    assert!("ACGT".bytes().all(|ch| !alphabet.is_ambiguous(ch)));
+    assert!(alphabet.is_ambiguous(b'a'));
+
+    for (label, (ch, count)) in report.frequencies.iter().enumerate() {
+        assert_eq!(alphabet.labels[*ch as usize], label as i32);
+        assert_eq!(*count, sample.chars().filter(|other| other == ch).count());
+    }
+
+    // report matches the parse and no assignment gives more balanced cores, including the default one
+    let cores = String::from_u8(sample.as_bytes(), &alphabet).cores.len();
+    assert_eq!(report.cores_per_kb, cores as f64 * 1000.0 / sample.len() as f64);
+    assert_eq!((report.mean_squared_gap, report.cores_per_kb), core_balance(sample.as_bytes(), &alphabet));
+    assert!(report.mean_squared_gap <= core_balance(sample.as_bytes(), &init_coefficients_default(false).unwrap()).0);
+
+    // evenly spaced cores are more balanced than the same number of clustered ones
+    let spaced = init_coefficients_map(HashMap::from([('A', 0), ('C', 1), ('G', 2), ('T', 3)]), false).unwrap();
+    assert!(core_balance(b"ACGTACGTACGTACGTACGT", &spaced).0 < core_balance(b"ACGTACGTACAAAAAAAAAA", &spaced).0);
+
+    // large alphabets are learned with heuristics
+    let text = "the quick brown fox jumps over the lazy dog, and then it sleeps all day long.";
+    let (alphabet, report) = init_coefficients_frequency(text.as_bytes(), false).unwrap();
+    assert!(report.evaluated > 4);
+    assert!(text.bytes().all(|ch| !alphabet.is_ambiguous(ch)));
+    assert!(report.frequencies.contains(&(' ', 15)));
+
+    // number of evaluations is limited, whatever the alphabet size is
+    let sample: Vec<u8> = random_sequence(4096, 8).iter().enumerate().map(|(index, ch)| (index * 131 + *ch as usize * 7) as u8).collect();
+    let (alphabet, report) = init_coefficients_frequency(&sample, false).unwrap();
+    assert_eq!(report.evaluated, LEARNING_EVALUATION_LIMIT);
+    assert_eq!(alphabet.dict_bit_size, 8);
+
+    assert!(matches!(init_coefficients_frequency(&[], false), Err(Error::EmptyAlphabet)));
+}
+
+
+#[test]
+fn test_encoding_permute() {
+    let alphabet = init_coefficients_protein(false).unwrap();
+
+    let permuted = alphabet.permute(42);
+
+    assert_eq!(permuted, alphabet.permute(42));
+    assert_ne!(permuted, alphabet.permute(43));
+    assert_eq!(permuted.dict_bit_size, alphabet.dict_bit_size);
+
+    let mut labels: Vec<i32> = "ACDEFGHIKLMNPQRSTVWYX*".bytes().map(|ch| permuted.labels[ch as usize]).collect();
+    for ch in "ACDEFGHIKLMNPQRSTVWYX*".bytes() {
+        // aliases keep sharing the label and characters decode the label back
+        assert_eq!(permuted.labels[ch.to_ascii_lowercase() as usize], permuted.labels[ch as usize]);
+        assert_eq!(permuted.characters[permuted.labels[ch as usize] as usize], ch as char);
+    }
+    labels.sort();
+    assert_eq!(labels, (0..22).collect::<Vec<i32>>());
+    assert!(permuted.is_ambiguous(b'B'));
+}
+
+
+#[test]
+fn test_encoding_complement() {
+    let default = init_coefficients_default(false).unwrap();
+    let complement = default.complement.unwrap();
+
+    assert_eq!((complement[b'A' as usize], complement[b'T' as usize], complement[b'c' as usize]), (b'T', b'A', b'g'));
+    assert_eq!(complement[b'N' as usize], b'N');
+    assert!(init_coefficients_protein(false).unwrap().complement.is_none());
+    assert!(matches!(init_coefficients_protein(false).unwrap().with_complement(&[('A', 'B')]), Err(Error::InvalidComplement { character: 'B' })));
+
+    let path = env::temp_dir().join(format!("rust-lcp-complement-{}.txt", std::process::id()));
+    let contents = [
+        ("@complement A T\n@complement C G\nA 0\nC 1\nG 2\nT 3\n", None),
+        ("A 0\nC 1\n@complement A U\n", Some(3)),
+        ("A 0\nC 1\n@complement A C\n@complement C A\n", Some(4)),
+    ];
+    for (content, line) in contents {
+        fs::write(&path, content).unwrap();
+        let result = init_coefficients_file(path.to_str().unwrap(), false);
+        match line {
+            None => assert_eq!(result.unwrap().complement.unwrap()[b'G' as usize], b'C'),
+            Some(line) => assert!(matches!(result, Err(Error::Line { line: l, .. }) if l == line)),
+        }
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+
+#[test]
+fn test_encoding_concurrent() {
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+
+    let default = init_coefficients_default(false).unwrap();
+    let custom = init_coefficients_map(HashMap::from([('A', 3), ('C', 0), ('T', 1), ('G', 2)]), false).unwrap();
+
+    let expected_default = String::from_u8(sequence.as_bytes(), &default).get_small_cores();
+    let expected_custom = String::from_u8(sequence.as_bytes(), &custom).get_small_cores();
+    assert_ne!(expected_default, expected_custom);
+
+    // both alphabets are used at the same time, none of them should affect the other
+    thread::scope(|scope| {
+        for (alphabet, expected) in [(&default, &expected_default), (&custom, &expected_custom)] {
+            scope.spawn(move || {
+                for _ in 0..100 {
+                    assert_eq!(&String::from_u8(sequence.as_bytes(), alphabet).get_small_cores(), expected);
+                }
+            });
+        }
+    });
 }
 
 
@@ -106,13 +421,12 @@ fn test_encoding_file() {
 //------------------------------------------------------------------------------------
 #[test]
 fn test_core_encoding_str() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let core: Core = Core::from_str(1, "ATGTC");
+    let core: Core = Core::from_str(1, "ATGTC", &alphabet).unwrap();
 
     assert_eq!(core.block_number, 2);
     assert_eq!(core.start_index, 6);
@@ -120,40 +434,218 @@ fn test_core_encoding_str() {
     assert_eq!(core.start, 1);
     assert_eq!(core.end, 6);
 
-    //drop(guard);
+    // characters that are not part of the alphabet, including the ones beyond a single byte, are errors
+    assert!(matches!(Core::from_str(0, "AğT", &alphabet), Err(Error::UnknownSymbol { character: 'ğ' })));
+    assert!(matches!(Core::from_str(0, "ANT", &alphabet), Err(Error::UnknownSymbol { character: 'N' })));
+    assert!(matches!(Core::from_char(0, 'ğ', &alphabet), Err(Error::UnknownSymbol { character: 'ğ' })));
+    assert!(matches!(Core::from_u8(0, b"ANA", &alphabet), Err(Error::UnknownSymbol { character: 'N' })));
+    assert!(matches!(Core::from_u8(0, &[b'A', 0xFF], &alphabet), Err(Error::UnknownSymbol { character: '\u{FF}' })));
+    assert_eq!(Core::from_char(0, 'ğ', &alphabet).unwrap_err().to_string(), "character 'ğ' is not part of the alphabet");
+
+    // positions are counted in characters, not bytes
+    let bytes = init_coefficients_bytes(false).unwrap();
+    let core: Core = Core::from_str(2, "aé", &bytes).unwrap();
+    assert_eq!((core.start, core.end, core.get_bit_count()), (2, 4, 16));
+}
+
+
+#[test]
+fn test_core_encoding_protein() {
+    // M=10, K=8, V=17, W=18, Y=19 with 5 bits each, labels straddle the blocks
+    let verbose = true;
+    let alphabet = init_coefficients_protein(verbose).unwrap();
+
+    let core: Core = Core::from_u8(3, "MKVWY".as_bytes(), &alphabet).unwrap();
+
+    assert_eq!(core.block_number, 4);
+    assert_eq!(core.start_index, 7);
+    assert_eq!(core.get_blocks(), [0b0, 0b10100100, 0b01000110, 0b01010011]);
+    assert_eq!(core.start, 3);
+    assert_eq!(core.end, 8);
+
+    let core: Core = Core::from_str(3, "MKVWY", &alphabet).unwrap();
+
+    assert_eq!(core.get_blocks(), [0b0, 0b10100100, 0b01000110, 0b01010011]);
+
+    let core: Core = Core::from_char(0, '*', &alphabet).unwrap();
+
+    assert_eq!(core.block_number, 1);
+    assert_eq!(core.start_index, 3);
+    assert_eq!(core.get_blocks(), [0b10101]);
+}
+
+
+#[test]
+fn test_core_encoding_labels() {
+    let core: Core = Core::from_labels(4, &[0x1ff, 0x001, 0x100], 9);
+
+    assert_eq!(core.block_number, 4);
+    assert_eq!(core.start_index, 5);
+    assert_eq!(core.get_blocks(), [0b111, 0b11111100, 0b00000011, 0b00000000]);
+    assert_eq!(core.start, 4);
+    assert_eq!(core.end, 7);
+
+    // same as the bit packing of the alphabet encodings
+    let alphabet = init_coefficients_default(false).unwrap();
+    assert!(Core::from_labels(0, &[0, 3, 2, 3, 1], 2) == Core::from_str(0, "ATGTC", &alphabet).unwrap());
 }
 
 
 #[test]
 fn test_core_encoding_ch() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let core: Core = Core::from_char(1, 'C');
+    let core: Core = Core::from_char(1, 'C', &alphabet).unwrap();
 
     assert_eq!(core.block_number, 1);
     assert_eq!(core.start_index, 6);
     assert_eq!(core.get_blocks(), [0b01]);
 
-    //drop(guard);
+}
+
+
+#[test]
+fn test_core_decode() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let protein = init_coefficients_protein(false).unwrap();
+
+    assert_eq!(Core::from_str(0, "GAGTC", &alphabet).unwrap().decode(&alphabet), Some(b"GAGTC".to_vec()));
+    assert_eq!(Core::from_u8(3, b"tttt", &alphabet).unwrap().decode(&alphabet), Some(b"TTTT".to_vec()));
+    assert_eq!(Core::from_char(1, 'C', &alphabet).unwrap().decode(&alphabet), Some(b"C".to_vec()));
+    assert_eq!(Core::from_u8(0, b"MKVLYW*", &protein).unwrap().decode(&protein), Some(b"MKVLYW*".to_vec()));
+
+    let mut core = Core::from_u8(0, b"GAGTC", &alphabet).unwrap();
+    core.compress(&Core::from_u8(0, b"GAGTA", &alphabet).unwrap());
+    assert_eq!(core.decode(&alphabet), None);
+}
+
+
+#[test]
+fn test_core_storage() {
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    // 32 characters fit in 64 bits, longer labels are stored in heap
+    let short = Core::from_u8(0, "ACGT".repeat(8).as_bytes(), &alphabet).unwrap();
+    let mut long = Core::from_u8(0, "ACGT".repeat(10).as_bytes(), &alphabet).unwrap();
+
+    assert!(short.blocks.is_inline());
+    assert!(!long.blocks.is_inline());
+    assert_eq!(long.block_number, 10);
+    assert_eq!(long.get_blocks(), [0b00011011; 10]);
+
+    long.compress(&short);
+    assert!(long.blocks.is_inline());
+
+    // cores can be moved to and shared between threads
+    let handle = thread::spawn(move || short.get_bit_count());
+    assert_eq!(handle.join().unwrap(), 64);
+}
+
+
+#[test]
+fn test_core_encode() {
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    // 40, 80 and 160 bits
+    let core1 = Core::from_u8(0, "ACGT".repeat(5).as_bytes(), &alphabet).unwrap();
+    let core2 = Core::from_u8(0, "ACGT".repeat(10).as_bytes(), &alphabet).unwrap();
+    let core3 = Core::from_u8(0, format!("{}{}", "T".repeat(16), "ACGT".repeat(16)).as_bytes(), &alphabet).unwrap();
+    let core4 = Core::from_u8(0, "ACGT".repeat(20).as_bytes(), &alphabet).unwrap();
+
+    assert_eq!(core1.encode(), 0x1B1B1B1B1B);
+    assert_eq!(core2.encode(), 0x1B1B1B1B1B1B1B1B);
+    assert_eq!(core1.encode_u128(), 0x1B1B1B1B1B);
+    assert_eq!(core2.encode_u128(), 0x1B1B1B1B1B1B1B1B1B1B);
+    assert_eq!(core3.encode_u128(), core4.encode_u128());
+
+    // hash covers whole label
+    assert_eq!(core2.encode_hash(), Core::from_u8(7, "ACGT".repeat(10).as_bytes(), &alphabet).unwrap().encode_hash());
+    assert_ne!(core3.encode_hash(), core4.encode_hash());
+    assert_ne!(core1.encode_hash(), core2.encode_hash());
+}
+
+
+#[test]
+fn test_core_fingerprint() {
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    // golden values of FINGERPRINT_VERSION 1, these should never change
+    assert_eq!(FINGERPRINT_VERSION, 1);
+    assert_eq!(Core::from_char(0, 'A', &alphabet).unwrap().fingerprint(0), 0xf86ede1559d114a3);
+    assert_eq!(Core::from_u8(0, b"AAAA", &alphabet).unwrap().fingerprint(0), 0x51ff61dd15d2083d);
+    assert_eq!(Core::from_u8(0, b"GAGTC", &alphabet).unwrap().fingerprint(0), 0xc8d7abcfe00c7419);
+    assert_eq!(Core::from_u8(0, b"GAGTC", &alphabet).unwrap().fingerprint(42), 0x685cf5346bee7d96);
+    assert_eq!(Core::from_u8(0, "ACGT".repeat(10).as_bytes(), &alphabet).unwrap().fingerprint(0), 0x107a40472a63524a);
+    assert_eq!(Core::from_u8(0, "T".repeat(100).as_bytes(), &alphabet).unwrap().fingerprint(7), 0x6be66d46c02409e7);
+
+    // independent of positions and of the way the label is built
+    let labels: Vec<u32> = b"GAGTC".iter().map(|ch| alphabet.labels[*ch as usize] as u32).collect();
+    assert_eq!(Core::from_labels(9, &labels, 2).fingerprint(0), 0xc8d7abcfe00c7419);
+    let parts = [Core::from_u8(0, b"GA", &alphabet).unwrap(), Core::from_u8(2, b"GTC", &alphabet).unwrap()];
+    assert_eq!(Core::from_cores(&parts).fingerprint(0), 0xc8d7abcfe00c7419);
+}
+
+
+#[test]
+fn test_core_format() {
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    let core1 = Core::from_u8(1, b"GAGTC", &alphabet).unwrap();
+    let core2 = Core::from_u8(0, "ACGT".repeat(3).as_bytes(), &alphabet).unwrap();
+
+    assert_eq!(core1.to_bit_string(), "1000101101");
+    assert_eq!(core2.to_bit_string(), "000110110001101100011011");
+    assert_eq!(Core::from_char(0, 'A', &alphabet).unwrap().to_bit_string(), "00");
+    assert_eq!(core1.to_string(), "[1, 6) 10 bits 0b1000101101");
+    assert_eq!(format!("{:?}", core1), "Core { start: 1, end: 6, bit_count: 10, label: 0b1000101101 }");
+}
+
+
+#[test]
+fn test_core_serialization() {
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    let core = Core::from_u8(1, b"GAGTC", &alphabet).unwrap();
+    assert_eq!(core.to_bytes(), [1, 1, 5, 10, 0b10, 0b00101101]);
+
+    let mut compressed = Core::from_u8(300, b"TTGTC", &alphabet).unwrap();
+    compressed.compress(&core);
+    let long = Core::from_u8(1 << 40, "ACGT".repeat(30).as_bytes(), &alphabet).unwrap();
+
+    for core in [core, compressed, long] {
+        let result = Core::from_bytes(&core.to_bytes()).unwrap();
+        assert!(result == core);
+        assert_eq!((result.start, result.end, result.to_bit_string()), (core.start, core.end, core.to_bit_string()));
+    }
+
+    assert!(matches!(Core::from_bytes(&[2, 1, 5, 10, 0b10, 0b00101101]), Err(Error::UnsupportedVersion { version: 2 })));
+    assert!(matches!(Core::from_bytes(&[1, 1, 5, 10, 0b10]), Err(Error::Corrupted { .. })));
+    assert!(matches!(Core::from_bytes(&[1, 1, 5, 10, 0b10, 0b00101101, 0]), Err(Error::Corrupted { .. })));
+    assert!(matches!(Core::from_bytes(&[1, 1, 5, 10, 0b110, 0b00101101]), Err(Error::Corrupted { .. })));
+    assert!(matches!(Core::from_bytes(&[1, 1, 0, 10, 0b10, 0b00101101]), Err(Error::Corrupted { .. })));
+
+    // bit count that would overflow the block arithmetic
+    let mut bytes: Vec<u8> = vec![1, 0, 1];
+    write_varint(u64::MAX, &mut bytes);
+    bytes.push(0b10);
+    assert!(matches!(Core::from_bytes(&bytes), Err(Error::Corrupted { reason: "unexpected end of bytes" })));
 }
 
 
 #[test]
 fn test_core_concatination() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
-    let cores: [Core; 5] = [Core::from_char(1, 'C'),
-    Core::from_char(1, 'G'),
-    Core::from_str(1, "ATC"),
-    Core::from_str(1, "TTTCAG"),
-    Core::from_char(1, 'A')];
+    let alphabet = init_coefficients_default(verbose).unwrap();
+    let cores: [Core; 5] = [Core::from_char(1, 'C', &alphabet).unwrap(),
+    Core::from_char(1, 'G', &alphabet).unwrap(),
+    Core::from_str(1, "ATC", &alphabet).unwrap(),
+    Core::from_str(1, "TTTCAG", &alphabet).unwrap(),
+    Core::from_char(1, 'A', &alphabet).unwrap()];
     println!("concatination started.");
 
     let core6 = Core::from_cores(&cores);
@@ -161,20 +653,18 @@ fn test_core_concatination() {
     println!("{}", core6.encode());
     assert_eq!(core6.encode(), 0b011000110111111101001000);
 
-    //drop(guard);
 }
 
 
 #[test]
 fn test_core_compression() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let core1: Core = Core::from_str(1, "ATGTC");
-    let mut core2: Core = Core::from_str(2, "TTGTC");
+    let core1: Core = Core::from_str(1, "ATGTC", &alphabet).unwrap();
+    let mut core2: Core = Core::from_str(2, "TTGTC", &alphabet).unwrap();
 
     core2.compress(&core1);
     print!("core1 :");
@@ -192,8 +682,8 @@ fn test_core_compression() {
 
     println!("Compression btw core1 and core2 completed successfully.");
 
-    let core3: Core = Core::from_str(1, "A");
-    let mut core4: Core = Core::from_str(2, "TAAAA");
+    let core3: Core = Core::from_str(1, "A", &alphabet).unwrap();
+    let mut core4: Core = Core::from_str(2, "TAAAA", &alphabet).unwrap();
 
     core4.compress(&core3);
     print!("core3 :");
@@ -211,8 +701,8 @@ fn test_core_compression() {
 
     println!("Compression btw core3 and core4 completed successfully.");
 
-    let core5: Core = Core::from_str(1, "T");
-    let mut core6: Core = Core::from_str(2, "TAAAA");
+    let core5: Core = Core::from_str(1, "T", &alphabet).unwrap();
+    let mut core6: Core = Core::from_str(2, "TAAAA", &alphabet).unwrap();
 
     core6.compress(&core5);
     print!("core5 :");
@@ -230,8 +720,8 @@ fn test_core_compression() {
 
     println!("Compression btw core5 and core6 completed successfully.");
 
-    let core7: Core = Core::from_str(1, "C");
-    let mut core8: Core = Core::from_str(2, "T");
+    let core7: Core = Core::from_str(1, "C", &alphabet).unwrap();
+    let mut core8: Core = Core::from_str(2, "T", &alphabet).unwrap();
 
     core8.compress(&core7);
     print!("core7 :");
@@ -249,91 +739,125 @@ fn test_core_compression() {
 
     println!("Compression btw core7 and core8 completed successfully.");
     
-    //drop(guard);
 }
 
 
 #[test]
 fn test_core_comparison_eq() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let core1: Core = Core::from_str(1, "ATGTGCT");
-    let core2: Core = Core::from_str(2, "ATGTGCT");
+    let core1: Core = Core::from_str(1, "ATGTGCT", &alphabet).unwrap();
+    let core2: Core = Core::from_str(2, "ATGTGCT", &alphabet).unwrap();
     
-    assert_eq!(core1 == core2, true);
+    assert!(core1 == core2);
 
-    let core3: Core = Core::from_str(1, "T");
-    let core4: Core = Core::from_str(2, "TTTT");
+    let core3: Core = Core::from_str(1, "T", &alphabet).unwrap();
+    let core4: Core = Core::from_str(2, "TTTT", &alphabet).unwrap();
 
-    assert_eq!(core3 == core4, false);
+    assert!(core3 != core4);
 
-    let core5: Core = Core::from_str(1, "A");
-    let core6: Core = Core::from_str(2, "AAAA");
+    let core5: Core = Core::from_str(1, "A", &alphabet).unwrap();
+    let core6: Core = Core::from_str(2, "AAAA", &alphabet).unwrap();
 
-    assert_eq!(core5 == core6, false);
+    assert!(core5 != core6);
 
-    let core7: Core = Core::from_str(1, "CC");
-    let core8: Core = Core::from_str(2, "CC");
+    let core7: Core = Core::from_str(1, "CC", &alphabet).unwrap();
+    let core8: Core = Core::from_str(2, "CC", &alphabet).unwrap();
 
-    assert_eq!(core7 == core8, true);
+    assert!(core7 == core8);
 
-    let core9: Core = Core::from_str(1, "TT");
-    let core10: Core = Core::from_str(2, "TT");
+    let core9: Core = Core::from_str(1, "TT", &alphabet).unwrap();
+    let core10: Core = Core::from_str(2, "TT", &alphabet).unwrap();
 
-    assert_eq!(core9 != core10, false);
+    assert!(core9 == core10);
 
-    let core11: Core = Core::from_str(1, "ATGGCT");
-    let core12: Core = Core::from_str(2, "ATGTGCT");
+    let core11: Core = Core::from_str(1, "ATGGCT", &alphabet).unwrap();
+    let core12: Core = Core::from_str(2, "ATGTGCT", &alphabet).unwrap();
     
-    assert_eq!(core11 != core12, true);
+    assert!(core11 != core12);
     
-    //drop(guard);
 }
 
 
 #[test]
+#[allow(clippy::nonminimal_bool)]
 fn test_core_comparison_cmp() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let core1: Core = Core::from_str(1, "ATGTGCT");
-    let core2: Core = Core::from_str(2, "ATGTGCT");
+    let core1: Core = Core::from_str(1, "ATGTGCT", &alphabet).unwrap();
+    let core2: Core = Core::from_str(2, "ATGTGCT", &alphabet).unwrap();
     
-    assert_eq!(core1 < core2, false);
+    assert!(!(core1 < core2));
 
-    let core3: Core = Core::from_str(1, "ATGTGCT");
-    let core4: Core = Core::from_str(2, "ATGTGCT");
+    let core3: Core = Core::from_str(1, "ATGTGCT", &alphabet).unwrap();
+    let core4: Core = Core::from_str(2, "ATGTGCT", &alphabet).unwrap();
 
-    assert_eq!(core3 <= core4, true);
+    assert!(core3 <= core4);
 
-    let core5: Core = Core::from_str(1, "A");
-    let core6: Core = Core::from_str(2, "AAAAA");
+    let core5: Core = Core::from_str(1, "A", &alphabet).unwrap();
+    let core6: Core = Core::from_str(2, "AAAAA", &alphabet).unwrap();
 
-    assert_eq!(core5 < core6, true);
+    assert!(core5 < core6);
 
-    let core7: Core = Core::from_str(1, "TC");
-    let core8: Core = Core::from_str(2, "CC");
+    let core7: Core = Core::from_str(1, "TC", &alphabet).unwrap();
+    let core8: Core = Core::from_str(2, "CC", &alphabet).unwrap();
 
-    assert_eq!(core7 > core8, true);
+    assert!(core7 > core8);
 
-    let core9: Core = Core::from_str(1, "TC");
-    let core10: Core = Core::from_str(2, "CC");
+    let core9: Core = Core::from_str(1, "TC", &alphabet).unwrap();
+    let core10: Core = Core::from_str(2, "CC", &alphabet).unwrap();
 
-    assert_eq!(core9 >= core10, true);
+    assert!(core9 >= core10);
 
-    let core11: Core = Core::from_str(1, "AGTGCT");
-    let core12: Core = Core::from_str(2, "ATGTGCT");
+    let core11: Core = Core::from_str(1, "AGTGCT", &alphabet).unwrap();
+    let core12: Core = Core::from_str(2, "ATGTGCT", &alphabet).unwrap();
     
-    assert_eq!(core11 > core12, false);
+    assert!(!(core11 > core12));
     
-    //drop(guard);
+}
+
+
+#[test]
+fn test_core_collections() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+
+    let mut string = String::new(sequence, &alphabet);
+    string.deepen();
+    let mut cores: Vec<Core> = string.cores.clone();
+    cores.extend(String::new(sequence, &alphabet).cores);
+
+    // cloned cores keep labels and positions
+    for (core, clone) in string.cores.iter().zip(cores.iter()) {
+        assert!(core == clone);
+        assert_eq!((core.start, core.end), (clone.start, clone.end));
+    }
+
+    // order is total and consistent with comparison operators and equality
+    cores.sort();
+    for window in cores.windows(2) {
+        assert!(window[0] <= window[1]);
+        assert_eq!(window[0] == window[1], window[0].cmp(&window[1]) == Ordering::Equal);
+    }
+
+    // cores with same label at different positions are the same element
+    let set: HashSet<Core> = cores.iter().cloned().collect();
+    let tree: BTreeSet<Core> = cores.iter().cloned().collect();
+    let mut moved = cores[0].clone();
+    moved.start += 100;
+    moved.end += 100;
+    cores.dedup();
+
+    assert_eq!(set.len(), cores.len());
+    assert_eq!(tree.len(), cores.len());
+    assert!(tree.iter().zip(cores.iter()).all(|(a, b)| a == b));
+    assert!(set.contains(&moved));
 }
 
 
@@ -344,20 +868,18 @@ fn test_core_comparison_cmp() {
 //------------------------------------------------------------------------------------
 #[test]
 fn test_string_init() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let string: String = String::new("GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG");
+    let string: String = String::new("GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG", &alphabet);
 
     assert_eq!(string.get_small_cores(), [0b100001, 0b10111, 0b11110, 0b11101011, 0b101110, 0b100001, 0b000101010100, 0b10010, 0b1001010100,
     0b10001, 0b100001, 0b10010, 0b10010100, 0b1000010, 0b100110, 0b10010100, 0b10010, 0b100111, 0b100010, 0b100111, 0b10010, 0b101011,
     0b111011, 0b100010, 0b101000, 0b100010, 0b100011, 0b10001, 0b10010, 0b101101, 0b11010111, 0b110111, 0b111011, 0b11000011, 0b110010,
     0b101001, 0b100111, 0b111011, 0b11010110]);
     
-    //drop(guard);
 }
 
 
@@ -368,32 +890,29 @@ fn test_string_init() {
 //------------------------------------------------------------------------------------
 #[test]
 fn test_string_init_from_u8() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
     let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
-    let string: String = String::from_u8(sequence.as_bytes());
+    let string: String = String::from_u8(sequence.as_bytes(), &alphabet);
 
     assert_eq!(string.get_small_cores(), [0b100001, 0b10111, 0b11110, 0b11101011, 0b101110, 0b100001, 0b000101010100, 0b10010, 0b1001010100,
     0b10001, 0b100001, 0b10010, 0b10010100, 0b1000010, 0b100110, 0b10010100, 0b10010, 0b100111, 0b100010, 0b100111, 0b10010, 0b101011,
     0b111011, 0b100010, 0b101000, 0b100010, 0b100011, 0b10001, 0b10010, 0b101101, 0b11010111, 0b110111, 0b111011, 0b11000011, 0b110010,
     0b101001, 0b100111, 0b111011, 0b11010110]);
     
-    //drop(guard);
 }
 
 
 #[test]
 fn test_string_compress() {
-    //let guard = mtx.lock().unwrap();
 
     // A/a=0, T/t=3, G/g=2, C/c=1
     let verbose = true;
-    init_coefficients_default(verbose);
+    let alphabet = init_coefficients_default(verbose).unwrap();
 
-    let mut string: String = String::new("GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG");
+    let mut string: String = String::new("GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG", &alphabet);
 
     assert_eq!(string.get_small_cores(), [0b100001, 0b10111, 0b11110, 0b11101011, 0b101110, 0b100001, 0b000101010100, 0b10010, 0b1001010100,
     0b10001, 0b100001, 0b10010, 0b10010100, 0b1000010, 0b100110, 0b10010100, 0b10010, 0b100111, 0b100010, 0b100111, 0b10010, 0b101011,
@@ -420,5 +939,811 @@ fn test_string_compress() {
     assert_eq!(string.get_small_cores(), [0b0100010001, 0b0100010001, 0b01000100100, 0b00100110110, 0b1101100001, 0b0001100001, 0b1000010001,
     0b00011000011, 0b10000110110, 0b1101100010, 0b1000100111, 0b01111000011, 0b0011100111]);
     
-    //drop(guard);
-}
\ No newline at end of file
+}
+
+
+#[test]
+fn test_string_init_protein() {
+    let verbose = true;
+    let alphabet = init_coefficients_protein(verbose).unwrap();
+    let sequence = "MKTAYIAKQRQISFVKSHFSRQLEERLGLIEVQAPILSRVGDGTQDNLSGAEEEEKAVQVKVKALPDAX*";
+
+    let expected: [u64; 26] = [0b100000000010011, 0b1110000001000, 0b11010111001101, 0b11010011101111, 0b11110010010001,
+    0b100010100001111, 0b1100010001111, 0b1001000110001101110, 0b110111001001, 0b10010010101001, 0b1110001110001,
+    0b11010000001100, 0b11000011101001, 0b11110111010001, 0b1010001000101, 0b1011000001101, 0b11010001001011,
+    0b10110100101111, 0b1010000000011, 0b1100011000110001101000, 0b10000000010001, 0b100010110110001,
+    0b100010100010001, 0b10000000001001, 0b10010110000010, 0b100000010100];
+
+    let string: String = String::from_u8(sequence.as_bytes(), &alphabet);
+    assert_eq!(string.get_small_cores(), expected);
+
+    // LEER and AEEEEK are cores with repetitions
+    assert_eq!((string.cores[7].start, string.cores[7].end), (22, 26));
+    assert_eq!((string.cores[19].start, string.cores[19].end), (50, 56));
+
+    let string: String = String::new(sequence, &alphabet);
+    assert_eq!(string.get_small_cores(), expected);
+}
+
+
+#[test]
+fn test_string_ambiguity_split() {
+    let verbose = true;
+    let alphabet = init_coefficients_default(verbose).unwrap();
+    let first = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAG";
+    let second = "CGCCAGCTGAGCTCAGGTGTGAGGAGATCACAG";
+    let third = "TCCTCTGTAATAGGCTGTCCG";
+    let sequence = format!("{}NNNN{}R{}", first, second, third);
+
+    let string: String = String::from_u8(sequence.as_bytes(), &alphabet);
+
+    assert_eq!(string.ambiguity, AmbiguityPolicy::Split);
+    assert_eq!(string.ambiguous_count, 5);
+    assert_eq!(string.segments, [(0, 33), (37, 70), (71, 92)]);
+
+    // each segment is parsed on its own
+    let mut expected: Vec<(usize, usize, u64)> = Vec::new();
+    for (offset, segment) in [(0, first), (37, second), (71, third)] {
+        for core in String::from_u8(segment.as_bytes(), &alphabet).cores {
+            expected.push((offset + core.start, offset + core.end, core.encode()));
+        }
+    }
+    let found: Vec<(usize, usize, u64)> = string.cores.iter().map(|core| (core.start, core.end, core.encode())).collect();
+    assert_eq!(found, expected);
+
+    let string: String = String::new(&sequence, &alphabet);
+    let found: Vec<(usize, usize, u64)> = string.cores.iter().map(|core| (core.start, core.end, core.encode())).collect();
+    assert_eq!(found, expected);
+}
+
+
+#[test]
+fn test_string_ambiguity_skip() {
+    let verbose = true;
+    let mut alphabet = init_coefficients_default(verbose).unwrap();
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGNNNNCGCCAGCTGAGCTCAGGTGYGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+
+    let split: String = String::from_u8(sequence.as_bytes(), &alphabet);
+
+    alphabet.ambiguity = AmbiguityPolicy::Skip;
+    let string: String = String::from_u8(sequence.as_bytes(), &alphabet);
+
+    assert_eq!(string.ambiguity, AmbiguityPolicy::Skip);
+    assert_eq!(string.ambiguous_count, 5);
+    assert_eq!(string.segments, [(0, sequence.len())]);
+
+    // no core contains ambiguous character
+    for core in &string.cores {
+        assert!(sequence[core.start..core.end].bytes().all(|ch| !alphabet.is_ambiguous(ch)));
+    }
+
+    // skipping windows gives the same cores as splitting
+    assert_eq!(string.get_small_cores(), split.get_small_cores());
+    assert!(string.cores.iter().zip(split.cores.iter()).all(|(lhs, rhs)| lhs.start == rhs.start && lhs.end == rhs.end));
+}
+
+
+#[test]
+fn test_string_ambiguity_map() {
+    let verbose = true;
+    let mut alphabet = init_coefficients_default(verbose).unwrap();
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGNNNNCGCCAGCTGAGCTCAGGTGRGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+
+    alphabet.ambiguity = AmbiguityPolicy::Map('A');
+    let string: String = String::from_u8(sequence.as_bytes(), &alphabet);
+    let expected: String = String::from_u8(sequence.replace(['N', 'R'], "A").as_bytes(), &alphabet);
+
+    assert_eq!(string.ambiguity, AmbiguityPolicy::Map('A'));
+    assert_eq!(string.ambiguous_count, 5);
+    assert_eq!(string.get_small_cores(), expected.get_small_cores());
+
+    // ambiguous characters cannot be mapped to another ambiguous character
+    alphabet.ambiguity = AmbiguityPolicy::Map('N');
+    let string: String = String::from_u8(sequence.as_bytes(), &alphabet);
+
+    assert_eq!(string.ambiguity, AmbiguityPolicy::Split);
+    assert_eq!(string.segments, [(0, 33), (37, 56), (57, 91)]);
+}
+
+
+#[test]
+fn test_string_init_bytes() {
+    let verbose = true;
+    let alphabet = init_coefficients_bytes(verbose).unwrap();
+    let data: [u8; 44] = [0x7f, 0x45, 0x4c, 0x46, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x03, 0x00, 0x3e, 0x00, 0x01, 0x00, 0x00, 0x00, 0xf0, 0xd4, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
+    0xff, 0xfe, 0x80, 0x81, 0xc3, 0xa9, 0x00, 0x10];
+
+    let string: String = String::from_u8(&data, &alphabet);
+
+    assert_eq!(string.ambiguous_count, 0);
+    assert_eq!(string.cores.iter().map(|core| (core.start, core.end)).collect::<Vec<_>>(),
+    [(4, 8), (6, 17), (16, 19), (18, 21), (20, 25), (23, 26), (26, 33), (31, 34), (32, 37), (35, 38), (37, 40), (39, 42)]);
+
+    // labels are the bytes themselves
+    for core in &string.cores {
+        assert_eq!(core.start_index, 0);
+        assert_eq!(core.get_blocks(), &data[core.start..core.end]);
+    }
+}
+
+
+#[test]
+fn test_string_init_text() {
+    let text = "Türkçe, 日本語のテキスト and English — naïve café: ğğğüş ıııı çay, お茶 ☕ değil mi?";
+    let alphabet = TextAlphabet::from_text(text);
+
+    let string: String = String::from_text(text, &alphabet);
+    let characters: Vec<char> = text.chars().collect();
+
+    assert!(!string.cores.is_empty());
+    assert_eq!(string.ambiguous_count, 0);
+    assert_eq!(string.segments, [(0, characters.len())]);
+
+    // positions are given as characters and bytes
+    for core in &string.cores {
+        let (start, end) = string.byte_range(core);
+        assert_eq!(text[start..end], characters[core.start..core.end].iter().collect::<std::string::String>());
+        assert_eq!(core.get_bit_count(), (core.end - core.start) * alphabet.dict_bit_size);
+    }
+
+    // characters out of the alphabet split the text
+    let alphabet = TextAlphabet::from_text("Türkçe, and English");
+    let string: String = String::from_text(text, &alphabet);
+    assert_eq!(string.segments[0], (0, 8));
+
+    // bytes are processed one by one, even if cores split multi-byte characters
+    let string: String = String::new(text, &init_coefficients_bytes(false).unwrap());
+    assert!(string.cores.iter().any(|core| !text.is_char_boundary(core.start)));
+}
+
+
+#[test]
+fn test_string_init_text_ascii() {
+    // labels preserve the order of characters, hence ascii text gives same cores as the byte alphabet
+    let text = "the quick brown fox jumps over the lazy dog, and then it sleeps all day long. zzzz...";
+    let alphabet = TextAlphabet::from_text(text);
+
+    let string: String = String::from_text(text, &alphabet);
+    let expected: String = String::from_u8(text.as_bytes(), &init_coefficients_bytes(false).unwrap());
+
+    assert_eq!(string.cores.len(), expected.cores.len());
+    for (core, other) in string.cores.iter().zip(expected.cores.iter()) {
+        assert_eq!((core.start, core.end), (other.start, other.end));
+        assert_eq!(string.byte_range(core), (other.start, other.end));
+    }
+}
+
+
+#[test]
+fn test_string_ensemble() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+    let seeds = [1, 2, 3, 4];
+
+    let strings = String::from_u8_ensemble(sequence.as_bytes(), &alphabet, &seeds);
+
+    assert_eq!(strings.len(), seeds.len());
+    for (string, seed) in strings.iter().zip(seeds.iter()) {
+        assert_eq!(string.get_small_cores(), String::from_u8(sequence.as_bytes(), &alphabet.permute(*seed)).get_small_cores());
+    }
+
+    let positions: Vec<Vec<(usize, usize)>> = strings.iter().map(|string| string.cores.iter().map(|core| (core.start, core.end)).collect()).collect();
+    let merged = String::merge(strings);
+    let merged_positions: Vec<(usize, usize)> = merged.cores.iter().map(|core| (core.start, core.end)).collect();
+
+    // merged positions are ordered, unique and contain positions of all parses
+    assert!(merged_positions.windows(2).all(|window| window[0] < window[1]));
+    assert!(positions.iter().flatten().all(|position| merged_positions.contains(position)));
+    assert!(merged_positions.iter().all(|position| positions.iter().any(|parse| parse.contains(position))));
+}
+
+
+#[test]
+fn test_string_sequences() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+
+    let mut string = String::new(sequence, &alphabet);
+    let sequences = string.get_sequences(&alphabet).unwrap();
+
+    assert_eq!(sequences.len(), string.cores.len());
+    for (start, end, substring) in sequences {
+        assert_eq!(substring, sequence.as_bytes()[start..end]);
+    }
+
+    string.deepen();
+    assert!(string.get_sequences(&alphabet).is_none());
+}
+
+
+#[test]
+fn test_string_encodings() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = format!("{}{}{}", "GGGACCTGGTGACCCCAGCCCACGAC", "A".repeat(70), "AGCCAAGCGCCAGCTGAGCTCAGGTGTGAGGAGATC");
+
+    let mut string = String::new(&sequence, &alphabet);
+
+    for _ in 0..3 {
+        let small = string.get_small_cores();
+        let large = string.get_large_cores();
+        let hashed = string.get_hashed_cores();
+
+        assert_eq!(small.len(), string.cores.len());
+        for (index, core) in string.cores.iter().enumerate() {
+            assert_eq!(small[index], large[index] as u64);
+            if core.get_bit_count() <= 64 {
+                assert_eq!(large[index], small[index] as u128);
+            }
+            assert_eq!(hashed[index], core.encode_hash());
+        }
+        string.deepen();
+    }
+
+    // exports of the previous levels are taken from the hierarchy
+    let mut string = String::new(&sequence, &alphabet);
+    assert_eq!(string.get_small_cores_of_level(2), None);
+    string.keep_hierarchy();
+
+    let mut exports = Vec::new();
+    for _ in 0..3 {
+        exports.push((string.get_small_cores(), string.get_large_cores(), string.get_hashed_cores(), string.get_fingerprints(7)));
+        string.deepen();
+    }
+
+    for (level, (small, large, hashed, fingerprints)) in ( 1.. ).zip(&exports) {
+        assert_eq!(string.get_small_cores_of_level(level).as_ref(), Some(small));
+        assert_eq!(string.get_large_cores_of_level(level).as_ref(), Some(large));
+        assert_eq!(string.get_hashed_cores_of_level(level).as_ref(), Some(hashed));
+        assert_eq!(string.get_fingerprints_of_level(level, 7).as_ref(), Some(fingerprints));
+    }
+    assert_eq!(string.get_small_cores_of_level(4), Some(string.get_small_cores()));
+    assert_eq!(string.get_large_cores_of_level(0), None);
+    assert_eq!(string.get_hashed_cores_of_level(5), None);
+}
+
+
+#[test]
+fn test_string_format() {
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    let string = String::new("GGGACCTGGTGACCCCAGCC", &alphabet);
+    let display = string.to_string();
+    let mut lines = display.lines();
+
+    assert_eq!(lines.next(), Some(format!("level 1: {} cores", string.cores.len()).as_str()));
+    assert!(lines.zip(string.cores.iter()).all(|(line, core)| line == core.to_string()));
+    assert_eq!(display.lines().count(), string.cores.len() + 1);
+    assert!(format!("{:?}", string).starts_with("String { level: 1, cores: [Core { start: "));
+}
+
+
+#[test]
+fn test_string_hierarchy() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = random_sequence(5000, 16);
+
+    let mut string = String::from_u8(&sequence, &alphabet);
+    string.keep_hierarchy();
+    string.deepen_multiple(3);
+
+    // retained levels are the same as the ones of a String without hierarchy
+    let mut plain = String::from_u8(&sequence, &alphabet);
+    for level in 1..=4 {
+        let cores = string.get_level(level).unwrap();
+        assert_eq!(cores, plain.cores.as_slice());
+        assert!(cores.iter().zip(plain.cores.iter()).all(|(a, b)| (a.start, a.end) == (b.start, b.end)));
+        plain.deepen();
+    }
+    assert!(string.get_level(5).is_none());
+    assert!(string.children(1, 0).is_none());
+
+    for level in 2..=4 {
+        let cores = string.get_level(level).unwrap();
+        let below = string.get_level(level - 1).unwrap();
+        assert!(!cores.is_empty());
+        for (index, core) in cores.iter().enumerate() {
+            let (start, end) = string.children(level, index).unwrap();
+            assert_eq!(core.start, below[start].start);
+            assert_eq!(core.end, below[end - 1].end);
+            assert!((start..end).all(|child| string.parents(level - 1, child).contains(&index)));
+        }
+    }
+
+    // ancestors cover their descendants
+    for index in 0..string.get_level(1).unwrap().len() {
+        let core = &string.get_level(1).unwrap()[index];
+        for ancestor in string.ancestors(1, index, 4) {
+            assert!(string.cores[ancestor].start <= core.start && core.end <= string.cores[ancestor].end);
+        }
+    }
+    assert_eq!(string.ancestors(2, 0, 3), string.parents(2, 0));
+
+    // parents are the same as the ones found by scanning every child range, also when starts of the ranges
+    // are not sorted, which happens after runs of cores (tandem repeats) if there are at least 3 compression iterations
+    let unit = random_sequence(4, 1001);
+    let repeats = [unit.repeat(3), random_sequence(50, 2001), unit.repeat(3)].concat();
+    let sequence = [random_sequence(3000, 1), repeats, random_sequence(2000, 3001)].concat();
+    let mut unsorted = false;
+    for count in [1, 2, 3, 5, MAX_COMPRESSION_ITERATION_COUNT] {
+        let mut string = String::from_u8(&sequence, &alphabet);
+        string.config = Config::new(count).unwrap();
+        string.keep_hierarchy();
+        string.deepen_multiple(3);
+        for level in 1..=3 {
+            let children = &string.hierarchy.as_ref().unwrap().children[level as usize - 1];
+            unsorted |= children.windows(2).any(|ranges| ranges[1].0 < ranges[0].0);
+            for index in 0..string.get_level(level).unwrap().len() {
+                let expected: Vec<usize> = children.iter().enumerate().filter(|(_, (start, end))| *start <= index && index < *end).map(|(parent, _)| parent).collect();
+                assert_eq!(string.parents(level, index), expected);
+            }
+        }
+    }
+    assert!(unsorted);
+
+    // hierarchy is not kept by default
+    assert!(plain.hierarchy.is_none());
+    assert!(plain.get_level(1).is_none());
+    assert!(plain.parents(1, 0).is_empty());
+}
+
+
+#[test]
+fn test_string_config() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = random_sequence(5000, 17);
+
+    assert!(matches!(Config::new(0), Err(Error::InvalidIterationCount { count: 0 })));
+    assert!(matches!(Config::new(MAX_COMPRESSION_ITERATION_COUNT + 1), Err(Error::InvalidIterationCount { .. })));
+    assert_eq!(Config::default(), Config::new(2).unwrap());
+
+    let mut default = String::from_u8(&sequence, &alphabet);
+    default.deepen_multiple(3);
+
+    for count in 1..=3 {
+        let config = Config::new(count).unwrap();
+        assert_eq!(config.core_length(), count + 3);
+
+        let mut string = String::from_u8(&sequence, &alphabet);
+        string.config = config;
+        string.keep_hierarchy();
+        string.deepen_multiple(3);
+
+        if count == 2 {
+            assert_eq!(string.cores, default.cores);
+        }
+
+        for level in 2..=4 {
+            let cores = string.get_level(level).unwrap();
+            let below = string.get_level(level - 1).unwrap();
+            assert!(!cores.is_empty());
+
+            // cores are built from core_length cores, unless they are runs of the same label
+            let lengths: Vec<usize> = (0..cores.len()).map(|index| string.children(level, index).unwrap()).map(|(start, end)| end - start).collect();
+            assert!(lengths.contains(&config.core_length()));
+            assert!(lengths.iter().all(|length| *length >= 3));
+
+            // first count cores are not compressed count times, so they are not used
+            let (start, _) = string.children(level, 0).unwrap();
+            assert!(start >= count);
+            assert!(cores.iter().all(|core| below.iter().any(|child| child.start == core.start)));
+        }
+    }
+}
+
+
+#[test]
+fn test_string_max_span() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let random = |seed: u64| std::string::String::from_utf8(random_sequence(2000, seed)).unwrap();
+    let sequence = format!("{}{}{}", random(18), "A".repeat(12000), random(19));
+
+    let mut limited = String::new(&sequence, &alphabet);
+    let mut unlimited = String::new(&sequence, &alphabet);
+    unlimited.config = unlimited.config.with_max_span(None);
+    assert_eq!(limited.config.max_span(), Some(10000));
+    assert!(limited.dropped.is_empty());
+
+    limited.deepen_multiple(3);
+    unlimited.deepen_multiple(3);
+
+    // run of 12000 characters is only covered without limit
+    assert_eq!(limited.dropped.len(), 3);
+    assert!(limited.dropped[0] > 0);
+    assert_eq!(unlimited.dropped, [0, 0, 0]);
+    assert!(!limited.cores.iter().any(|core| core.start <= 2000 && 14000 <= core.end));
+    assert!(unlimited.cores.iter().any(|core| core.start <= 2000 && 14000 <= core.end));
+
+    // the only difference is the dropped cores
+    let mut small = String::new(&sequence, &alphabet);
+    small.config = small.config.with_max_span(Some(20));
+    small.deepen();
+    let mut plain = String::new(&sequence, &alphabet);
+    plain.config = plain.config.with_max_span(None);
+    plain.deepen();
+    assert_eq!(small.cores.len() + small.dropped[0], plain.cores.len());
+    assert!(small.cores.iter().all(|core| plain.cores.iter().any(|other| (core.start, core.end) == (other.start, other.end))));
+}
+
+
+#[test]
+fn test_string_canonical() {
+    let sequence = "GGGACCTGGTGACCCCAGCCCACGACAGCCAAGCGCCAGCTGAGNCTCAGGTGTGAGGAGATCACAGTCCTCTGTAATAGGCTGTCCG";
+
+    let mut skip = init_coefficients_default(false).unwrap();
+    skip.ambiguity = AmbiguityPolicy::Skip;
+    let mut map = init_coefficients_default(false).unwrap();
+    map.ambiguity = AmbiguityPolicy::Map('A');
+
+    for alphabet in [init_coefficients_default(false).unwrap(), skip, map] {
+        let complement = alphabet.complement.unwrap();
+        let reverse: Vec<u8> = sequence.bytes().rev().map(|ch| complement[ch as usize]).collect();
+
+        let string = String::from_u8_canonical(sequence.as_bytes(), &alphabet).unwrap();
+        let other = String::from_u8_canonical(&reverse, &alphabet).unwrap();
+        let strands = string.strands.clone().unwrap();
+
+        // cores of both strands are the same, at mirrored positions with opposite orientations
+        assert_eq!(string.cores.len(), other.cores.len());
+        assert_eq!(strands.len(), string.cores.len());
+        for (core, strand) in string.cores.iter().zip(strands.iter()) {
+            let index = other.cores.iter().position(|o| (o.start, o.end) == (sequence.len() - core.end, sequence.len() - core.start)).unwrap();
+            assert!(other.cores[index] == *core);
+            // substrings that are the same on both strands are always forward, only mapped cores contain N
+            let mapped = |strand: &[u8]| -> Vec<u8> { strand.iter().map(|&ch| if ch == b'N' { b'A' } else { ch }).collect() };
+            let palindrome = Core::from_u8(0, &mapped(&sequence.as_bytes()[core.start..core.end]), &alphabet).unwrap() == Core::from_u8(0, &mapped(&reverse[sequence.len() - core.end..sequence.len() - core.start]), &alphabet).unwrap();
+            if palindrome {
+                assert_eq!((other.strands.as_ref().unwrap()[index], *strand), (Strand::Forward, Strand::Forward));
+            } else {
+                assert_ne!(other.strands.as_ref().unwrap()[index], *strand);
+            }
+        }
+
+        // labels are the smaller one of both strands and contain forward cores
+        let forward = String::from_u8(sequence.as_bytes(), &alphabet);
+        assert!(forward.cores.iter().all(|core| string.cores.iter().any(|c| (c.start, c.end) == (core.start, core.end) && c <= core)));
+        assert!(strands.contains(&Strand::Forward) && strands.contains(&Strand::Reverse));
+        assert_eq!(string.ambiguous_count, 1);
+    }
+
+    let alphabet = init_coefficients_default(false).unwrap();
+    let mut string = String::from_u8_canonical(sequence.as_bytes(), &alphabet).unwrap();
+    string.deepen();
+    assert!(string.strands.is_none());
+    assert!(matches!(String::from_u8_canonical(b"ACDEF", &init_coefficients_protein(false).unwrap()), Err(Error::MissingComplement)));
+}
+
+
+#[test]
+fn test_string_serialization() {
+    let alphabet = init_coefficients_default(false).unwrap();
+    let sequence = random_sequence(5000, 20);
+
+    let mut string = String::from_u8(&sequence, &alphabet);
+    string.config = Config::new(3).unwrap();
+
+    for level in 1..=4 {
+        let bytes = string.to_bytes();
+        let result = String::from_bytes(&bytes).unwrap();
+
+        assert_eq!(bytes[0], 1);
+        assert_eq!(result.level, level);
+        assert_eq!(result.cores, string.cores);
+        assert!(result.cores.iter().zip(string.cores.iter()).all(|(a, b)| (a.start, a.end) == (b.start, b.end)));
+        // positions are delta encoded, so most of the cores need a single byte per position
+        assert!(bytes.len() < string.cores.iter().map(|core| 3 + core.get_blocks().len() + 2).sum::<usize>());
+
+        assert!(matches!(String::from_bytes(&bytes[..bytes.len() - 1]), Err(Error::Corrupted { .. })));
+        string.deepen();
+    }
+
+    assert_eq!(String::from_bytes(&[1, 1, 0]).unwrap().cores.len(), 0);
+    assert!(matches!(String::from_bytes(&[0, 1, 0]), Err(Error::UnsupportedVersion { version: 0 })));
+    assert!(matches!(String::from_bytes(&[1, 1, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 1]), Err(Error::Corrupted { .. })));
+
+    // second start overflows, as both deltas are near i64::MAX
+    let mut bytes: Vec<u8> = vec![1, 1, 2];
+    for _ in 0..2 {
+        write_varint(zigzag(i64::MAX - 1), &mut bytes);
+        bytes.extend([3, 6, 0b011011]);
+    }
+    assert!(matches!(String::from_bytes(&bytes), Err(Error::Corrupted { .. })));
+
+    // bit count of the label would overflow the block arithmetic
+    let mut bytes: Vec<u8> = vec![1, 1, 1, 0, 3];
+    write_varint(u64::MAX, &mut bytes);
+    bytes.push(0b011011);
+    assert!(matches!(String::from_bytes(&bytes), Err(Error::Corrupted { reason: "unexpected end of bytes" })));
+}
+
+
+/// Reference level-1 parse of a sequence without ambiguous characters, written directly from the rules.
+fn reference_cores(sequence: &[u8], labels: &[i32; 256]) -> Vec<(usize, usize)> {
+    let mut cores = Vec::new();
+    let mut index = 1;
+    while index + 3 < sequence.len() {
+        let (left, center) = (sequence[index], sequence[index + 1]);
+        if left != center && center == sequence[index + 2] {
+            let mut end = index + 2;
+            while end < sequence.len() && sequence[end] == center { end += 1; }
+            if end == sequence.len() { break; }
+            cores.push((index, end + 1));
+        } else if left != center {
+            let w: Vec<i32> = sequence[index - 1..index + 4].iter().map(|ch| labels[*ch as usize]).collect();
+            if (w[2] < w[1] && w[2] < w[3]) || (w[2] > w[1] && w[2] > w[3] && w[1] >= w[0] && w[3] >= w[4]) {
+                cores.push((index, index + 3));
+            }
+        }
+        index += 1;
+    }
+    cores
+}
+
+
+/// Sequence and text that the golden cores are given for.
+const GOLDEN_SEQUENCE: &str = "GGGACCTGGTGACNNCCCAGCCCacgacagCCAAGCGNCCAGCTTTTTTTGAGNCTCAGGTGTGAGGRAGATCACAGTCCTCTGTAATAGG";
+const GOLDEN_TEXT: &str = "naïve café, ÇAĞRI çağrı — ğüşö üüü öğretmen! kayık";
+const GOLDEN_TEXT_ALPHABET: &str = "abcçdefgğhıijklmnoöprsştuüvyzï é";
+
+/// Level-1 cores (start, end and label) of the golden sequence for each ambiguity policy, given by `String::new`
+/// and `String::from_u8` before they were unified into `String::parse`. Both constructors gave the same cores.
+/// Skip policy should give the same cores as Split policy, since both drop exactly the windows that contain an
+/// ambiguous character, so `GOLDEN_SPLIT` is used for both.
+const GOLDEN_SPLIT: &[(usize, usize, &str)] = &[
+    (2, 5, "100001"), (3, 7, "00010111"), (5, 8, "011110"), (6, 10, "11101011"),
+    (8, 11, "101110"), (17, 20, "010010"), (19, 24, "1001010100"), (22, 25, "010001"),
+    (25, 28, "100001"), (27, 30, "010010"), (29, 33, "10010100"), (31, 35, "01000010"),
+    (39, 42, "010010"), (41, 44, "100111"), (42, 51, "011111111111111110"), (56, 59, "010010"),
+    (57, 61, "00101011"), (60, 63, "111011"), (63, 66, "100010"), (69, 72, "100011"),
+    (72, 75, "010001"), (74, 77, "010010"), (76, 79, "101101"), (77, 81, "11010111"),
+    (80, 83, "110111"), (82, 85, "111011"), (84, 88, "11000011"), (87, 90, "110010"),
+];
+const GOLDEN_MAP: &[(usize, usize, &str)] = &[
+    (2, 5, "100001"), (3, 7, "00010111"), (5, 8, "011110"), (6, 10, "11101011"),
+    (8, 11, "101110"), (10, 13, "100001"), (12, 16, "01000001"), (14, 19, "0001010100"),
+    (17, 20, "010010"), (19, 24, "1001010100"), (22, 25, "010001"), (25, 28, "100001"),
+    (27, 30, "010010"), (29, 33, "10010100"), (31, 35, "01000010"), (34, 37, "100110"),
+    (36, 39, "100001"), (37, 41, "00010100"), (39, 42, "010010"), (41, 44, "100111"),
+    (42, 51, "011111111111111110"), (50, 53, "100010"), (52, 55, "100001"), (54, 57, "011101"),
+    (56, 59, "010010"), (57, 61, "00101011"), (60, 63, "111011"), (63, 66, "100010"),
+    (64, 68, "00101000"), (66, 70, "10000010"), (69, 72, "100011"), (72, 75, "010001"),
+    (74, 77, "010010"), (76, 79, "101101"), (77, 81, "11010111"), (80, 83, "110111"),
+    (82, 85, "111011"), (84, 88, "11000011"), (87, 90, "110010"),
+];
+
+/// Level-1 cores of the golden text, given by `String::from_text` before it was unified into `String::parse_symbols`.
+const GOLDEN_FROM_TEXT: &[(usize, usize, &str)] = &[
+    (4, 7, "001010000000011"), (6, 9, "000110000100110"), (18, 21, "110000000111101"), (20, 23, "111011000111110"),
+    (26, 29, "111011110011111"), (29, 32, "110110000011100"), (30, 35, "0000011100111001110000000"), (33, 36, "111000000011011"),
+    (35, 38, "110111110110001"), (37, 40, "100010010110011"), (45, 48, "010110000110110"),
+];
+
+
+#[test]
+fn test_string_parse_differential() {
+    let mut skip = init_coefficients_default(false).unwrap();
+    skip.ambiguity = AmbiguityPolicy::Skip;
+    let mut map = init_coefficients_default(false).unwrap();
+    map.ambiguity = AmbiguityPolicy::Map('A');
+    let alphabets = [
+        (init_coefficients_default(false).unwrap(), "ACGTacgtN"),
+        (skip, "ACGTN"),
+        (map, "ACGTN"),
+        (init_coefficients_protein(false).unwrap(), "ACDEFGHIKLMNPQRSTVWYX*"),
+        (init_coefficients_bytes(false).unwrap(), "ab"),
+    ];
+
+    for (alphabet, characters) in &alphabets {
+        for (seed, length) in [0, 2, 3, 5, 17, 100, 1000].into_iter().enumerate() {
+            // symbols of the random sequence are mapped to the characters, starting from a different one for each sequence
+            let sequence: std::string::String = random_sequence(length, seed as u64).iter()
+                .map(|ch| characters.as_bytes()[(b"ACGTN".iter().position(|symbol| symbol == ch).unwrap() + 3 * seed) % characters.len()] as char)
+                .collect();
+            let bytes = sequence.as_bytes().to_vec();
+
+            let expected = String::from_u8(&bytes, alphabet);
+            for string in [String::new(&sequence, alphabet), String::parse(&sequence, alphabet), String::parse(&bytes, alphabet), String::parse(bytes.clone(), alphabet)] {
+                assert_eq!(string.cores, expected.cores);
+                assert!(string.cores.iter().zip(expected.cores.iter()).all(|(a, b)| (a.start, a.end) == (b.start, b.end)));
+                assert_eq!((string.ambiguous_count, &string.segments), (expected.ambiguous_count, &expected.segments));
+            }
+
+            // cores of each segment are the ones given by the rules
+            if alphabet.ambiguity == AmbiguityPolicy::Split {
+                let mut positions = Vec::new();
+                for (start, end) in &expected.segments {
+                    positions.extend(reference_cores(&bytes[*start..*end], &alphabet.labels).into_iter().map(|(s, e)| (s + start, e + start)));
+                }
+                assert_eq!(expected.cores.iter().map(|core| (core.start, core.end)).collect::<Vec<_>>(), positions);
+                assert!(expected.cores.iter().all(|core| *core == Core::from_u8(core.start, &bytes[core.start..core.end], alphabet).unwrap()));
+            }
+        }
+    }
+
+    // cores are the same as the ones given by the constructors before they were unified
+    let labeled = |string: &String| -> Vec<(usize, usize, std::string::String)> {
+        string.cores.iter().map(|core| (core.start, core.end, core.to_bit_string())).collect()
+    };
+    let golden = |cores: &[(usize, usize, &str)]| -> Vec<(usize, usize, std::string::String)> {
+        cores.iter().map(|&(start, end, label)| (start, end, label.to_string())).collect()
+    };
+
+    for (policy, cores) in [(AmbiguityPolicy::Split, GOLDEN_SPLIT), (AmbiguityPolicy::Skip, GOLDEN_SPLIT), (AmbiguityPolicy::Map('A'), GOLDEN_MAP)] {
+        let mut alphabet = init_coefficients_default(false).unwrap();
+        alphabet.ambiguity = policy;
+        for string in [String::new(GOLDEN_SEQUENCE, &alphabet), String::from_u8(GOLDEN_SEQUENCE.as_bytes(), &alphabet), String::parse(GOLDEN_SEQUENCE, &alphabet)] {
+            assert_eq!(labeled(&string), golden(cores));
+        }
+    }
+
+    let alphabet = TextAlphabet::from_chars(GOLDEN_TEXT_ALPHABET.chars());
+    assert_eq!(labeled(&String::from_text(GOLDEN_TEXT, &alphabet)), golden(GOLDEN_FROM_TEXT));
+}
+
+
+#[test]
+fn test_string_parallel() {
+    // random sequence with a long run in the middle
+    let sequence = [random_sequence(15000, 25), vec![b'A'; 12000], random_sequence(15000, 26)].concat();
+
+    let mut skip = init_coefficients_default(false).unwrap();
+    skip.ambiguity = AmbiguityPolicy::Skip;
+    let mut map = init_coefficients_default(false).unwrap();
+    map.ambiguity = AmbiguityPolicy::Map('C');
+
+    let configs = [Config::default(), Config::new(1).unwrap(), Config::new(3).unwrap().with_max_span(Some(200)), Config::default().with_max_span(None)];
+    // pools are reused for every string, level and compression iteration
+    let pools: Vec<ThreadPool> = [1, 2, 3, 8, 64].into_iter().map(ThreadPool::new).collect();
+    assert_eq!(pools.iter().map(ThreadPool::threads).collect::<Vec<_>>(), [1, 2, 3, 8, 64]);
+
+    for alphabet in [init_coefficients_default(false).unwrap(), skip, map] {
+        for config in configs {
+            let mut expected = String::from_u8(&sequence, &alphabet);
+            expected.config = config;
+            expected.keep_hierarchy();
+            expected.deepen_multiple(4);
+
+            for pool in &pools {
+                let mut string = String::from_u8_parallel(&sequence, &alphabet, pool);
+                assert_eq!((string.ambiguous_count, &string.segments), (expected.ambiguous_count, &expected.segments));
+                string.config = config;
+                string.keep_hierarchy();
+                string.deepen_parallel(4, pool);
+
+                // positions are compared as well, as cores are compared by their labels
+                assert_eq!(string.to_bytes(), expected.to_bytes());
+                assert_eq!(string.dropped, expected.dropped);
+                for level in 1..=4 {
+                    let bytes = |string: &String| string.get_level(level).unwrap().iter().map(|core| core.to_bytes()).collect::<Vec<_>>();
+                    assert_eq!(bytes(&string), bytes(&expected));
+                }
+                assert_eq!(string.hierarchy.unwrap().children, expected.hierarchy.as_ref().unwrap().children);
+            }
+        }
+    }
+
+    // too short strings and more threads than cores
+    let alphabet = init_coefficients_default(false).unwrap();
+    for sequence in ["", "AC", "ACGTA", "ACGTAGGGGGGGGG", "ACGTACNNGTCAGTACGGT"] {
+        let mut expected = String::new(sequence, &alphabet);
+        expected.deepen_multiple(2);
+        let mut string = String::from_u8_parallel(sequence.as_bytes(), &alphabet, &pools[4]);
+        string.deepen_parallel(2, &pools[4]);
+        assert_eq!(string.to_bytes(), expected.to_bytes());
+        assert_eq!(string.segments, expected.segments);
+    }
+}
+
+
+//------------------------------------------------------------------------------------
+//------------------------------------------------------------------------------------
+// TESTS FOR STREAM
+//------------------------------------------------------------------------------------
+//------------------------------------------------------------------------------------
+#[test]
+fn test_stream_reader() {
+    let sequence = random_sequence(20000, 0);
+
+    let mut skip = init_coefficients_default(false).unwrap();
+    skip.ambiguity = AmbiguityPolicy::Skip;
+    let mut map = init_coefficients_default(false).unwrap();
+    map.ambiguity = AmbiguityPolicy::Map('C');
+
+    for alphabet in [init_coefficients_default(false).unwrap(), skip, map] {
+        let expected = String::from_u8(&sequence, &alphabet);
+        let positions: Vec<(usize, usize)> = expected.cores.iter().map(|core| (core.start, core.end)).collect();
+
+        for capacity in [1, 2, 3, 5, 7, 64, 4096, 1 << 16] {
+            let mut reader = CoreReader::new(BufReader::with_capacity(capacity, sequence.as_slice()), &alphabet);
+            let cores: Vec<Core> = reader.by_ref().collect::<Result<Vec<Core>, Error>>().unwrap();
+
+            assert_eq!(cores, expected.cores);
+            assert_eq!(cores.iter().map(|core| (core.start, core.end)).collect::<Vec<_>>(), positions);
+            assert_eq!(reader.ambiguous_count(), expected.ambiguous_count);
+        }
+    }
+
+    // sequence ending with a run and too short sequences
+    let alphabet = init_coefficients_default(false).unwrap();
+    for sequence in ["ACGTAGGGGGGGGG", "ACGTAGGGGGGGGGT", "AC", ""] {
+        let cores: Vec<Core> = CoreReader::new(BufReader::with_capacity(2, sequence.as_bytes()), &alphabet).map(|core| core.unwrap()).collect();
+        assert_eq!(cores, String::new(sequence, &alphabet).cores);
+    }
+
+    // errors of the reader are returned once
+    struct Failing;
+    impl Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("failure"))
+        }
+    }
+    let mut reader = CoreReader::new(BufReader::new(Failing), &alphabet);
+    assert!(matches!(reader.next(), Some(Err(Error::Read { .. }))));
+    assert!(reader.next().is_none());
+}
+
+
+#[test]
+fn test_stream_scanner() {
+    // random sequence with runs longer than the inline storage and different ambiguous characters, including consecutive ones
+    let mut sequence = random_sequence(20000, 24);
+    sequence.iter_mut().skip(1).step_by(2).filter(|ch| **ch == b'N').for_each(|ch| *ch = b'R');
+
+    let mut skip = init_coefficients_default(false).unwrap();
+    skip.ambiguity = AmbiguityPolicy::Skip;
+    let mut map = init_coefficients_default(false).unwrap();
+    map.ambiguity = AmbiguityPolicy::Map('C');
+    let mut invalid = init_coefficients_default(false).unwrap();
+    invalid.ambiguity = AmbiguityPolicy::Map('N');
+
+    for alphabet in [init_coefficients_default(false).unwrap(), skip, map, invalid, init_coefficients_protein(false).unwrap()] {
+        let expected = String::from_u8(&sequence, &alphabet);
+        assert!(expected.cores.iter().any(|core| !core.blocks.is_inline()));
+
+        for seed in [0, 42] {
+            let cores: Vec<(usize, usize, u64)> = CoreScanner::new(&sequence, &alphabet, seed).collect();
+            assert_eq!(cores, expected.cores.iter().map(|core| (core.start, core.end, core.fingerprint(seed))).collect::<Vec<_>>());
+        }
+    }
+
+    // sequences ending with a run or an ambiguous character and too short sequences
+    let alphabet = init_coefficients_default(false).unwrap();
+    for sequence in ["ACGTAGGGGGGGGG", "ACGTAGGGGGGGGGT", "ACGTACNNGTCAGN", "NNNN", "AC", ""] {
+        let cores: Vec<(usize, usize, u64)> = CoreScanner::new(sequence.as_bytes(), &alphabet, 7).collect();
+        assert_eq!(cores, String::new(sequence, &alphabet).cores.iter().map(|core| (core.start, core.end, core.fingerprint(7))).collect::<Vec<_>>());
+    }
+}
+
+
+#[test]
+fn test_stream_deepener() {
+    // random sequence with a long run in the middle
+    let sequence = [random_sequence(15000, 23), vec![b'A'; 12000], random_sequence(15000, 24)].concat();
+    let alphabet = init_coefficients_default(false).unwrap();
+
+    for count in 1..=3 {
+        for max_span in [Some(10000), Some(200), None] {
+            let config = Config::new(count).unwrap().with_max_span(max_span);
+            let mut string = String::from_u8(&sequence, &alphabet);
+            string.config = config;
+
+            for level in 1..=5 {
+                let reader = CoreReader::new(BufReader::with_capacity(1000, sequence.as_slice()), &alphabet);
+                let mut deepener = CoreDeepener::new(reader, config, level);
+                let cores: Vec<Core> = deepener.by_ref().map(|core| core.unwrap()).collect();
+                string.deepen();
+
+                assert_eq!(cores, string.cores);
+                assert!(cores.iter().zip(string.cores.iter()).all(|(a, b)| (a.start, a.end) == (b.start, b.end)));
+                assert_eq!(deepener.dropped(), string.dropped);
+            }
+        }
+    }
+
+    // level 0 yields the given cores and errors are passed through
+    let cores: Vec<Result<Core, Error>> = vec![Core::from_u8(0, b"ACG", &alphabet), Err(Error::Corrupted { reason: "test" })];
+    let mut deepener = CoreDeepener::new(cores.into_iter(), Config::default(), 0);
+    assert!(deepener.next().unwrap().is_ok());
+    assert!(matches!(deepener.next(), Some(Err(Error::Corrupted { .. }))));
+    assert!(deepener.next().is_none());
+}